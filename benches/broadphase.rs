@@ -0,0 +1,94 @@
+//! Compares finding overlapping planets with the spatial hash against checking every pair.
+//! Run with `cargo bench --bench broadphase`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use david_game::planet::{Planet, PlanetSettings};
+use david_game::random_generator::get_rand_generator;
+use david_game::spatial_hash::SpatialHash;
+use david_game::world::BROADPHASE_CELL_SIZE;
+use macroquad::math::Vec2;
+
+const ITERATIONS: u32 = 20;
+
+fn spawn_planets(count: usize) -> Vec<Planet> {
+    let rng = get_rand_generator(1);
+    // keep the density about the same as in the game, so the number of overlaps stays realistic
+    let side = (count as f32).sqrt() * 400f32;
+    (0..count)
+        .map(|i| {
+            Planet::new(
+                Vec2 {
+                    x: rng.gen_range(0f32, side),
+                    y: rng.gen_range(0f32, side),
+                },
+                Vec2::ZERO,
+                rng.gen_range(10f32, 100f32),
+                &PlanetSettings::default(),
+                i as u64,
+            )
+        })
+        .collect()
+}
+
+fn brute_force(planets: &[Planet], pairs: &mut Vec<(usize, usize)>) {
+    for i in 0..planets.len() {
+        for j in (i + 1)..planets.len() {
+            if (planets[i].position - planets[j].position).length()
+                < planets[i].radius + planets[j].radius
+            {
+                pairs.push((i, j));
+            }
+        }
+    }
+}
+
+fn spatial_hash(planets: &[Planet], hash: &mut SpatialHash, pairs: &mut Vec<(usize, usize)>) {
+    hash.rebuild(
+        planets
+            .iter()
+            .map(|planet| (planet.position, planet.radius)),
+    );
+    hash.overlapping_pairs(pairs);
+}
+
+fn time(mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>14} {:>14} {:>8}",
+        "planets", "brute force", "spatial hash", "pairs"
+    );
+    for count in [100, 1_000, 5_000, 20_000] {
+        let planets = spawn_planets(count);
+        let mut hash = SpatialHash::new(BROADPHASE_CELL_SIZE);
+        let mut brute_force_pairs = Vec::new();
+        let mut hash_pairs = Vec::new();
+
+        let brute_force_time = time(|| {
+            brute_force_pairs.clear();
+            brute_force(black_box(&planets), &mut brute_force_pairs);
+        });
+        let hash_time = time(|| {
+            hash_pairs.clear();
+            spatial_hash(black_box(&planets), &mut hash, &mut hash_pairs);
+        });
+
+        hash_pairs.sort_unstable();
+        assert_eq!(brute_force_pairs, hash_pairs);
+        println!(
+            "{:>8} {:>14?} {:>14?} {:>8}",
+            count,
+            brute_force_time,
+            hash_time,
+            hash_pairs.len()
+        );
+    }
+}
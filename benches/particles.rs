@@ -0,0 +1,80 @@
+//! How long a step of the particle pool takes with lots of particles alive, counting the
+//! emitters spawning their replacements and moving along with their owners.
+//! Run with `cargo bench --bench particles`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use david_game::particle::ParticleGroup;
+use david_game::particle_controller::ParticleController;
+use david_game::particle_effect::ParticleEffect;
+use david_game::particle_pool::ParticlePool;
+use david_game::planet::PlanetSettings;
+use david_game::world::FIXED_DELTA_TIME;
+use macroquad::math::Vec2;
+
+const EMITTERS: usize = 1_000;
+const LIFESPAN: f32 = 0.5;
+// one second of steps, enough for the particle count to settle
+const WARM_UP_STEPS: u32 = 120;
+const STEPS: u32 = 240;
+
+// emitters spread over a grid, half of them drifting and taking their particles along
+fn spawn_emitters(particle_count: usize) -> Vec<(Vec2, ParticleController)> {
+    let time_per_particle = EMITTERS as f32 * LIFESPAN / particle_count as f32;
+    (0..EMITTERS)
+        .map(|i| {
+            let position = Vec2 {
+                x: (i % 40) as f32 * 200f32,
+                y: (i / 40) as f32 * 200f32,
+            };
+            // planet particles, with some gravity and drag so all of it gets computed
+            let effect = ParticleEffect {
+                time_per_particle,
+                lifespan: LIFESPAN,
+                gravity: [0f32, 200f32],
+                drag: 0.5,
+                ..PlanetSettings::default().particles
+            };
+            let controller = effect.controller(50f32, ParticleGroup::Planet);
+            (position, controller)
+        })
+        .collect()
+}
+
+fn step(pool: &mut ParticlePool, emitters: &mut [(Vec2, ParticleController)]) {
+    for (i, (position, controller)) in emitters.iter_mut().enumerate() {
+        controller.update(pool, FIXED_DELTA_TIME, *position);
+        if i % 2 == 0 {
+            let change = Vec2::new(30f32, -20f32) * FIXED_DELTA_TIME;
+            *position += change;
+            controller.inherit_movement(pool, change);
+        }
+    }
+    pool.update(FIXED_DELTA_TIME);
+}
+
+fn main() {
+    println!("{:>10} {:>10} {:>14}", "particles", "alive", "per step");
+    for particle_count in [10_000, 50_000, 100_000] {
+        // some room, since the count wobbles around the target
+        let mut pool = ParticlePool::new(particle_count * 2, 1);
+        let mut emitters = spawn_emitters(particle_count);
+        for _ in 0..WARM_UP_STEPS {
+            step(&mut pool, &mut emitters);
+        }
+
+        let start = Instant::now();
+        for _ in 0..STEPS {
+            step(black_box(&mut pool), &mut emitters);
+        }
+        let step_time: Duration = start.elapsed() / STEPS;
+
+        println!(
+            "{:>10} {:>10} {:>14?}",
+            particle_count,
+            pool.particles().len(),
+            step_time
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use macroquad::math::{Rect, Vec2};
+use macroquad::texture::{FilterMode, Image, Texture2D};
+
+/// Name of the atlas image in the assets directory, its sprites are listed in `ATLAS_INDEX`.
+pub const ATLAS_IMAGE: &str = "atlas.png";
+/// One `name x y width height` line (in pixels) per sprite in `ATLAS_IMAGE`.
+pub const ATLAS_INDEX: &str = "atlas.txt";
+
+/// Sprite names the game looks for.
+pub const PLAYER_SPRITE: &str = "player";
+pub const PLANET_SPRITE: &str = "planet";
+pub const DANGER_ZONE_SPRITE: &str = "danger_zone";
+
+/// Width and height in pixels of the texture particles are drawn with.
+pub const PARTICLE_TEXTURE_SIZE: u16 = 64;
+
+/// A part of a texture, the whole of it for sprites that come from their own PNG.
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    pub texture: Texture2D,
+    pub source: Rect,
+}
+
+impl Sprite {
+    pub fn from_texture(texture: Texture2D) -> Self {
+        let source = Rect::new(0f32, 0f32, texture.width(), texture.height());
+        Self { texture, source }
+    }
+}
+
+/// All loaded sprites by name. Anything that isn't here is drawn the old way, with particles
+/// only, so the game runs fine without any assets at all.
+#[derive(Default)]
+pub struct Assets {
+    pub sprites: HashMap<String, Sprite>,
+    /// What every particle looks like, tinted with its color. Without it particles are drawn as
+    /// plain circles one by one, which is a lot slower.
+    pub particle_texture: Option<Texture2D>,
+}
+
+impl Assets {
+    /// Where assets are looked for, next to wherever the game is started from.
+    pub fn default_path() -> PathBuf {
+        PathBuf::from("assets")
+    }
+
+    /// Loads the atlas (if there is one) and every other PNG in `directory`, which is named
+    /// after its file. Needs a window, since textures live on the GPU. A missing directory
+    /// gives no sprites, files that can't be read are skipped with a message.
+    pub fn load(directory: &Path) -> Self {
+        let mut assets = Self {
+            sprites: HashMap::new(),
+            particle_texture: Some(soft_circle_texture(PARTICLE_TEXTURE_SIZE)),
+        };
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == ErrorKind::NotFound => return assets,
+            Err(error) => {
+                eprintln!(
+                    "couldn't read assets from {}: {}",
+                    directory.display(),
+                    error
+                );
+                return assets;
+            }
+        };
+
+        let atlas_image = directory.join(ATLAS_IMAGE);
+        if atlas_image.exists() {
+            match load_atlas(&atlas_image, &directory.join(ATLAS_INDEX)) {
+                Ok(sprites) => assets.sprites.extend(sprites),
+                Err(error) => eprintln!("couldn't load atlas {}: {}", atlas_image.display(), error),
+            }
+        }
+
+        // single images win over atlas sprites of the same name
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "png")
+                || path.file_name().is_some_and(|name| name == ATLAS_IMAGE)
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match load_texture(&path) {
+                Ok(texture) => {
+                    assets
+                        .sprites
+                        .insert(name.to_string(), Sprite::from_texture(texture));
+                }
+                Err(error) => eprintln!("couldn't load texture {}: {}", path.display(), error),
+            }
+        }
+        assets
+    }
+
+    pub fn sprite(self: &Self, name: &str) -> Option<&Sprite> {
+        self.sprites.get(name)
+    }
+}
+
+/// A white disc that is opaque in the middle and fades out toward its edge, so particles drawn
+/// with it look like the circles they used to be, just with softer edges.
+pub fn soft_circle_texture(size: u16) -> Texture2D {
+    let mut bytes = Vec::with_capacity(size as usize * size as usize * 4);
+    let center = size as f32 * 0.5;
+    for y in 0..size {
+        for x in 0..size {
+            // from the pixel's center, 1 is the edge of the disc
+            let distance =
+                Vec2::new(x as f32 + 0.5 - center, y as f32 + 0.5 - center).length() / center;
+            let alpha = ((1f32 - distance) / 0.25).clamp(0f32, 1f32);
+            bytes.extend_from_slice(&[255, 255, 255, (alpha * 255f32) as u8]);
+        }
+    }
+    let texture = Texture2D::from_rgba8(size, size, &bytes);
+    texture.set_filter(FilterMode::Linear);
+    texture
+}
+
+pub fn load_texture(path: &Path) -> Result<Texture2D> {
+    let bytes = fs::read(path)?;
+    let image = Image::from_file_with_format(&bytes, None)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Linear);
+    Ok(texture)
+}
+
+/// Loads the atlas image at `image_path` and cuts it into the sprites listed in `index_path`.
+pub fn load_atlas(image_path: &Path, index_path: &Path) -> Result<HashMap<String, Sprite>> {
+    let index = fs::read_to_string(index_path)?;
+    let texture = load_texture(image_path)?;
+    let regions = parse_atlas_index(&index, texture.size())?;
+    Ok(regions
+        .into_iter()
+        .map(|(name, source)| {
+            let sprite = Sprite {
+                texture: texture.clone(),
+                source,
+            };
+            (name, sprite)
+        })
+        .collect())
+}
+
+/// Reads `name x y width height` lines, empty lines and lines starting with `#` are skipped.
+/// Every region has to be inside an image of `image_size`.
+pub fn parse_atlas_index(text: &str, image_size: Vec2) -> Result<Vec<(String, Rect)>> {
+    let mut regions = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "line {}: expected `name x y width height`, got `{}`",
+                    line_number + 1,
+                    line
+                ),
+            )
+        };
+        let mut fields = line.split_whitespace();
+        let name = fields.next().ok_or_else(invalid)?;
+        let mut numbers = [0f32; 4];
+        for number in &mut numbers {
+            *number = fields
+                .next()
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(invalid)?;
+        }
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+        let [x, y, width, height] = numbers;
+        let is_inside = x >= 0f32
+            && y >= 0f32
+            && width > 0f32
+            && height > 0f32
+            && x + width <= image_size.x
+            && y + height <= image_size.y;
+        if !is_inside {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "line {}: `{}` isn't inside the {}x{} atlas",
+                    line_number + 1,
+                    name,
+                    image_size.x,
+                    image_size.y
+                ),
+            ));
+        }
+        regions.push((name.to_string(), Rect::new(x, y, width, height)));
+    }
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATLAS_SIZE: Vec2 = Vec2::new(256f32, 128f32);
+
+    #[test]
+    fn reads_regions_and_skips_comments() {
+        let text = "# name x y width height\n\nplayer 0 0 64 64\n  planet 64 0 128.5 128  \n";
+        let regions = parse_atlas_index(text, ATLAS_SIZE).unwrap();
+        assert_eq!(
+            regions,
+            [
+                ("player".to_string(), Rect::new(0f32, 0f32, 64f32, 64f32)),
+                ("planet".to_string(), Rect::new(64f32, 0f32, 128.5, 128f32)),
+            ]
+        );
+        assert!(parse_atlas_index("", ATLAS_SIZE).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for text in [
+            "player",
+            "player 0 0 64",
+            "player 0 0 64 64 64",
+            "player 0 zero 64 64",
+            "player 0,0 64 64",
+        ] {
+            let error = parse_atlas_index(text, ATLAS_SIZE).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", text);
+        }
+        let error = parse_atlas_index("player 0 0 64 64\nplanet 1 2", ATLAS_SIZE).unwrap_err();
+        assert!(error.to_string().starts_with("line 2:"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_regions_outside_the_atlas() {
+        for text in [
+            "player -1 0 64 64",
+            "player 0 0 0 64",
+            "player 0 0 64 -64",
+            "player 200 0 64 64",
+            "player 0 100 64 64",
+            "player nan 0 64 64",
+            "player 0 0 inf 64",
+        ] {
+            let error = parse_atlas_index(text, ATLAS_SIZE).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", text);
+        }
+        // right up to the edge is fine
+        assert!(parse_atlas_index("player 192 64 64 64", ATLAS_SIZE).is_ok());
+    }
+}
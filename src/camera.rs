@@ -0,0 +1,135 @@
+use macroquad::camera::{set_camera, set_default_camera, Camera2D};
+use macroquad::math::{Rect, Vec2};
+use macroquad::window::{screen_height, screen_width};
+use serde::Deserialize;
+
+use crate::player::Player;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraSettings {
+    /// Zoom when standing still, 1 is everything at its actual size.
+    pub max_zoom: f32,
+    /// Zoom at very high speeds, so there is always room to see what's coming.
+    pub min_zoom: f32,
+    /// How quickly the view zooms out with speed (per unit of speed).
+    pub zoom_per_speed: f32,
+    /// How quickly the zoom catches up with the speed (per second), higher is snappier.
+    pub zoom_smoothing: f32,
+    /// How far (in seconds of movement) the view looks ahead of the player.
+    pub look_ahead: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            max_zoom: 1f32,
+            min_zoom: 0.35,
+            zoom_per_speed: 0.0012,
+            zoom_smoothing: 2f32,
+            look_ahead: 0.02,
+        }
+    }
+}
+
+/// The view onto the world, in units that make sense for the game (pixels and degrees) and
+/// turned into a macroquad `Camera2D` for drawing. Between `begin_world` and `end_world`
+/// everything is drawn in world coordinates and macroquad applies zoom, rotation and offset, so
+/// every renderer gets them for free.
+pub struct GameCamera {
+    /// World position the camera looks at, and the center of zoom and rotation.
+    pub target: Vec2,
+    /// Where `target` ends up on screen, in pixels from the center of the screen.
+    pub offset: Vec2,
+    /// Screen pixels per world unit.
+    pub zoom: f32,
+    /// Rotation of the view in degrees.
+    pub rotation: f32,
+    pub settings: CameraSettings,
+}
+
+impl GameCamera {
+    pub fn new(settings: CameraSettings) -> Self {
+        Self {
+            target: Vec2::ZERO,
+            offset: Vec2::ZERO,
+            zoom: settings.max_zoom,
+            rotation: 0f32,
+            settings,
+        }
+    }
+
+    /// Centers the view on the player and zooms out the faster they go. `alpha` is how far
+    /// the frame is between the last two simulation steps, `frame_time` the real time since the
+    /// last frame (the zoom is smoothed over it).
+    pub fn follow(self: &mut Self, player: &Player, alpha: f32, frame_time: f32) {
+        let settings = &self.settings;
+        let target_zoom = (settings.max_zoom
+            / (1f32 + player.velocity.length() * settings.zoom_per_speed))
+            .clamp(settings.min_zoom, settings.max_zoom);
+        self.zoom +=
+            (target_zoom - self.zoom) * (1f32 - (-settings.zoom_smoothing * frame_time).exp());
+
+        self.offset = -player.velocity * settings.look_ahead * self.zoom;
+        self.target = player.previous_position.lerp(player.position, alpha);
+    }
+
+    pub fn camera_2d(self: &Self) -> Camera2D {
+        // macroquad's camera works in normalized device coordinates (-1 to 1 across the screen)
+        let width = screen_width();
+        let height = screen_height();
+        Camera2D {
+            target: self.target,
+            zoom: Vec2 {
+                x: 2f32 * self.zoom / width,
+                y: 2f32 * self.zoom / height,
+            },
+            offset: Vec2 {
+                x: 2f32 * self.offset.x / width,
+                y: -2f32 * self.offset.y / height,
+            },
+            rotation: self.rotation,
+            ..Default::default()
+        }
+    }
+
+    /// From here on everything is drawn in world coordinates.
+    pub fn begin_world(self: &Self) {
+        set_camera(&self.camera_2d());
+    }
+
+    /// Back to drawing in screen pixels, e.g. for the HUD.
+    pub fn end_world(self: &Self) {
+        set_default_camera();
+    }
+
+    pub fn world_to_screen(self: &Self, position: Vec2) -> Vec2 {
+        self.camera_2d().world_to_screen(position)
+    }
+
+    pub fn screen_to_world(self: &Self, position: Vec2) -> Vec2 {
+        self.camera_2d().screen_to_world(position)
+    }
+
+    /// The part of the world that's on screen, as an axis aligned box around it when the view is
+    /// rotated.
+    pub fn visible_area(self: &Self) -> Rect {
+        let camera = self.camera_2d();
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(screen_width(), 0f32),
+            Vec2::new(0f32, screen_height()),
+            Vec2::new(screen_width(), screen_height()),
+        ]
+        .map(|corner| camera.screen_to_world(corner));
+        let min = corners.into_iter().reduce(Vec2::min).unwrap_or_default();
+        let max = corners.into_iter().reduce(Vec2::max).unwrap_or_default();
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+}
+
+impl Default for GameCamera {
+    fn default() -> Self {
+        Self::new(CameraSettings::default())
+    }
+}
@@ -0,0 +1,73 @@
+use macroquad::math::Vec2;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollisionSettings {
+    /// 1 keeps all the energy along the collision normal (the old behaviour), 0 makes bodies
+    /// stop moving toward each other without bouncing.
+    pub restitution: f32,
+    /// Coulomb friction coefficient, how much the bodies slow each other down sideways.
+    pub friction: f32,
+}
+
+impl Default for CollisionSettings {
+    fn default() -> Self {
+        Self {
+            restitution: 1f32,
+            friction: 0f32,
+        }
+    }
+}
+
+/// The parts of a circular body a collision needs.
+pub struct Body<'a> {
+    pub position: &'a mut Vec2,
+    pub velocity: &'a mut Vec2,
+    pub radius: f32,
+    pub mass: f32,
+}
+
+/// Pushes two overlapping circles apart and exchanges momentum between them. Heavier bodies are
+/// moved less and keep more of their velocity.
+pub fn resolve_collision(a: &mut Body, b: &mut Body, settings: &CollisionSettings) {
+    let offset = *b.position - *a.position;
+    let distance = offset.length();
+    // exactly on top of each other, any direction works
+    let normal = if distance > 0f32 {
+        offset / distance
+    } else {
+        Vec2::X
+    };
+    let inverse_mass_a = 1f32 / a.mass;
+    let inverse_mass_b = 1f32 / b.mass;
+    let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+
+    // position
+    let penetration = a.radius + b.radius - distance;
+    if penetration > 0f32 {
+        *a.position -= normal * penetration * inverse_mass_a / inverse_mass_sum;
+        *b.position += normal * penetration * inverse_mass_b / inverse_mass_sum;
+    }
+
+    // velocity
+    let relative_velocity = *b.velocity - *a.velocity;
+    let normal_speed = relative_velocity.dot(normal);
+    if normal_speed > 0f32 {
+        // already moving apart
+        return;
+    }
+    let impulse = -(1f32 + settings.restitution) * normal_speed / inverse_mass_sum;
+    *a.velocity -= normal * impulse * inverse_mass_a;
+    *b.velocity += normal * impulse * inverse_mass_b;
+
+    // friction
+    let tangent_velocity = relative_velocity - normal * normal_speed;
+    let tangent_speed = tangent_velocity.length();
+    if tangent_speed > 0f32 {
+        let tangent = tangent_velocity / tangent_speed;
+        let friction_impulse = (tangent_speed / inverse_mass_sum).min(settings.friction * impulse);
+        *a.velocity += tangent * friction_impulse * inverse_mass_a;
+        *b.velocity -= tangent * friction_impulse * inverse_mass_b;
+    }
+}
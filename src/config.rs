@@ -0,0 +1,480 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::camera::CameraSettings;
+use crate::collision::CollisionSettings;
+use crate::controls::{Action, Bindings};
+use crate::danger_zone::ZoneSettings;
+use crate::difficulty::{Curve, DifficultyProfile};
+use crate::enemy::EnemySettings;
+use crate::particle_effect::{ParticleEffect, SpawnShape, MIN_TIME_PER_PARTICLE};
+use crate::planet::PlanetSettings;
+use crate::player::PlayerSettings;
+use crate::projectile::ProjectileSettings;
+use crate::render::RenderSettings;
+
+/// Everything that can be tuned without recompiling, read from a TOML file with one table per
+/// part of the game (`[player]`, `[planet]`, `[enemy]`, `[projectile]`, `[zone]`, `[camera]`,
+/// `[collision]`, `[difficulty]`, `[controls]` and `[render]`). Anything left out keeps its
+/// default, unknown keys are an error so typos don't go unnoticed. That includes what everything
+/// looks like, as particle effects.
+///
+/// The simulation depends on these values, so a replay only plays back the same way with the
+/// config it was recorded with.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub player: PlayerSettings,
+    pub planet: PlanetSettings,
+    pub enemy: EnemySettings,
+    pub projectile: ProjectileSettings,
+    pub zone: ZoneSettings,
+    pub camera: CameraSettings,
+    pub collision: CollisionSettings,
+    pub difficulty: DifficultyProfile,
+    pub controls: Bindings,
+    pub render: RenderSettings,
+}
+
+impl Config {
+    /// Where the config is looked for, next to wherever the game is started from.
+    pub fn default_path() -> PathBuf {
+        PathBuf::from("config.toml")
+    }
+
+    /// A missing file is the default config.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Identifies the values the simulation depends on, so a replay can tell whether it's played
+    /// back with the config it was recorded with. Particle effects, the camera, controls and
+    /// drawing don't change what happens, so they don't count.
+    pub fn simulation_hash(self: &Self) -> u64 {
+        let looks = ParticleEffect::default();
+        let mut player = self.player;
+        player.particles = looks;
+        player.trail_particles = looks;
+        let mut planet = self.planet;
+        planet.particles = looks;
+        let mut zone = self.zone;
+        zone.bottom_particles = looks;
+        zone.side_particles = looks;
+        let values = format!(
+            "{:?}",
+            (player, planet, zone, self.collision, &self.difficulty)
+        );
+        // FNV-1a, the standard library's hasher may change between Rust versions
+        values.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// Checks for values the game can't work with, and lists all of them at once.
+    pub fn validate(self: &Self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        let player = &self.player;
+        positive(&mut problems, "player.radius", player.radius);
+        positive(&mut problems, "player.density", player.density);
+        not_negative(&mut problems, "player.drag", player.drag);
+        not_negative(
+            &mut problems,
+            "player.grab_acceleration",
+            player.grab_acceleration,
+        );
+        not_negative(
+            &mut problems,
+            "player.boost_acceleration",
+            player.boost_acceleration,
+        );
+        not_negative(&mut problems, "player.reel_speed", player.reel_speed);
+        if player.max_rope_length < player.radius || !player.max_rope_length.is_finite() {
+            problems.push(format!(
+                "player.max_rope_length ({}) has to be a number, at least player.radius ({})",
+                player.max_rope_length, player.radius
+            ));
+        }
+        // fully elastic would never pull back at all
+        if !(0f32..1f32).contains(&player.rope_elasticity) {
+            problems.push(format!(
+                "player.rope_elasticity has to be at least 0 and less than 1, but is {}",
+                player.rope_elasticity
+            ));
+        }
+        positive(
+            &mut problems,
+            "player.max_grab_range",
+            player.max_grab_range,
+        );
+        not_negative(
+            &mut problems,
+            "player.shoot_cooldown",
+            player.shoot_cooldown,
+        );
+        positive(
+            &mut problems,
+            "player.ammo_refill_time",
+            player.ammo_refill_time,
+        );
+        particles(&mut problems, "player.particles", &player.particles);
+        particles(
+            &mut problems,
+            "player.trail_particles",
+            &player.trail_particles,
+        );
+
+        let planet = &self.planet;
+        positive(&mut problems, "planet.density", planet.density);
+        not_negative(&mut problems, "planet.max_spin", planet.max_spin);
+        particles(&mut problems, "planet.particles", &planet.particles);
+
+        let enemy = &self.enemy;
+        particles(
+            &mut problems,
+            "enemy.chaser_particles",
+            &enemy.chaser_particles,
+        );
+        particles(
+            &mut problems,
+            "enemy.orbiter_particles",
+            &enemy.orbiter_particles,
+        );
+        particles(&mut problems, "enemy.mine_particles", &enemy.mine_particles);
+        particles(
+            &mut problems,
+            "projectile.trail_particles",
+            &self.projectile.trail_particles,
+        );
+
+        let zone = &self.zone;
+        positive(&mut problems, "zone.bottom_size[0]", zone.bottom_size[0]);
+        positive(&mut problems, "zone.bottom_size[1]", zone.bottom_size[1]);
+        positive(&mut problems, "zone.side_size[0]", zone.side_size[0]);
+        positive(&mut problems, "zone.side_size[1]", zone.side_size[1]);
+        not_negative(&mut problems, "zone.bottom_speed", zone.bottom_speed);
+        // the player starts at the origin, a zone already touching them there kills them right
+        // away. Side zones move up and down with the player, so only their distance counts
+        let min_side_distance = zone.side_size[0] * 0.5 + player.radius;
+        if zone.side_distance < min_side_distance || !zone.side_distance.is_finite() {
+            problems.push(format!(
+                "zone.side_distance has to be at least {} (half of zone.side_size[0] plus \
+                 player.radius) to keep the side zones off the player at the start, but is {}",
+                min_side_distance, zone.side_distance
+            ));
+        }
+        let [bottom_x, bottom_y] = zone.bottom_position;
+        // far enough away on either axis is fine, NaN never is
+        let is_bottom_clear = bottom_x.abs() >= zone.bottom_size[0] * 0.5 + player.radius
+            || bottom_y.abs() >= zone.bottom_size[1] * 0.5 + player.radius;
+        if !is_bottom_clear {
+            problems.push(format!(
+                "zone.bottom_position [{}, {}] puts the bottom zone on the player at the start",
+                bottom_x, bottom_y
+            ));
+        }
+        particles(
+            &mut problems,
+            "zone.bottom_particles",
+            &zone.bottom_particles,
+        );
+        particles(&mut problems, "zone.side_particles", &zone.side_particles);
+        // particles are spawned inside the zone, so they have to fit
+        for (name, size, effect) in [
+            (
+                "zone.bottom_particles",
+                zone.bottom_size,
+                &zone.bottom_particles,
+            ),
+            ("zone.side_particles", zone.side_size, &zone.side_particles),
+        ] {
+            let longest_side = size[0].max(size[1]);
+            let radius_per_size = effect.radius_per_size.max(effect.end_radius_per_size());
+            if effect.shape == SpawnShape::Box
+                && longest_side * radius_per_size * 2f32 >= size[0].min(size[1])
+            {
+                problems.push(format!(
+                    "{}.radius_per_size is {}, too big for particles to fit into the zone",
+                    name, radius_per_size
+                ));
+            }
+        }
+
+        let camera = &self.camera;
+        positive(&mut problems, "camera.min_zoom", camera.min_zoom);
+        if camera.max_zoom < camera.min_zoom || !camera.max_zoom.is_finite() {
+            problems.push(format!(
+                "camera.max_zoom ({}) has to be a number, at least camera.min_zoom ({})",
+                camera.max_zoom, camera.min_zoom
+            ));
+        }
+        not_negative(
+            &mut problems,
+            "camera.zoom_per_speed",
+            camera.zoom_per_speed,
+        );
+        positive(
+            &mut problems,
+            "camera.zoom_smoothing",
+            camera.zoom_smoothing,
+        );
+
+        let collision = &self.collision;
+        in_range(
+            &mut problems,
+            "collision.restitution",
+            collision.restitution,
+            0f32,
+            1f32,
+        );
+        not_negative(&mut problems, "collision.friction", collision.friction);
+
+        let difficulty = &self.difficulty;
+        for (name, curve) in [
+            (
+                "difficulty.planets_per_chunk",
+                &difficulty.planets_per_chunk,
+            ),
+            (
+                "difficulty.min_planet_radius",
+                &difficulty.min_planet_radius,
+            ),
+            (
+                "difficulty.max_planet_radius",
+                &difficulty.max_planet_radius,
+            ),
+            ("difficulty.max_planet_drift", &difficulty.max_planet_drift),
+            (
+                "difficulty.bottom_zone_acceleration",
+                &difficulty.bottom_zone_acceleration,
+            ),
+            (
+                "difficulty.enemies_per_chunk",
+                &difficulty.enemies_per_chunk,
+            ),
+        ] {
+            not_negative_curve(&mut problems, name, curve);
+        }
+        for (altitude, _) in &difficulty.min_planet_radius.points {
+            let min_radius = difficulty.min_planet_radius.value_at(*altitude);
+            if min_radius <= 0f32 {
+                problems.push(format!(
+                    "difficulty.min_planet_radius has to be more than 0, but is {} at altitude {}",
+                    min_radius, altitude
+                ));
+            }
+        }
+        for &(altitude, _) in difficulty
+            .min_planet_radius
+            .points
+            .iter()
+            .chain(&difficulty.max_planet_radius.points)
+        {
+            let min_radius = difficulty.min_planet_radius.value_at(altitude);
+            let max_radius = difficulty.max_planet_radius.value_at(altitude);
+            if max_radius < min_radius {
+                problems.push(format!(
+                    "difficulty.max_planet_radius ({}) is below difficulty.min_planet_radius \
+                     ({}) at altitude {}",
+                    max_radius, min_radius, altitude
+                ));
+            }
+        }
+
+        for (name, action) in [
+            ("controls.grab", Action::Grab),
+            ("controls.boost", Action::Boost),
+            ("controls.shoot", Action::Shoot),
+            ("controls.pause", Action::Pause),
+            ("controls.reel_in", Action::ReelIn),
+            ("controls.reel_out", Action::ReelOut),
+            ("controls.confirm", Action::Confirm),
+        ] {
+            if self.controls.of(action).is_empty() {
+                problems.push(format!("{} needs at least one key or button", name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidData, problems.join("\n")))
+        }
+    }
+}
+
+fn positive(problems: &mut Vec<String>, name: &str, value: f32) {
+    if value <= 0f32 || !value.is_finite() {
+        problems.push(format!("{} has to be more than 0, but is {}", name, value));
+    }
+}
+
+fn not_negative(problems: &mut Vec<String>, name: &str, value: f32) {
+    if value < 0f32 || !value.is_finite() {
+        problems.push(format!("{} can't be negative, but is {}", name, value));
+    }
+}
+
+fn in_range(problems: &mut Vec<String>, name: &str, value: f32, min: f32, max: f32) {
+    if !(min..=max).contains(&value) {
+        problems.push(format!(
+            "{} has to be between {} and {}, but is {}",
+            name, min, max, value
+        ));
+    }
+}
+
+fn particles(problems: &mut Vec<String>, name: &str, effect: &ParticleEffect) {
+    // any more often and a single emitter could fill the whole pool in one frame
+    if effect.time_per_particle < MIN_TIME_PER_PARTICLE || !effect.time_per_particle.is_finite() {
+        problems.push(format!(
+            "{}.time_per_particle has to be at least {}, but is {}",
+            name, MIN_TIME_PER_PARTICLE, effect.time_per_particle
+        ));
+    }
+    positive(problems, &format!("{}.lifespan", name), effect.lifespan);
+    if let SpawnShape::Ring { radius } = effect.shape {
+        not_negative(problems, &format!("{}.shape.radius", name), radius);
+    }
+    not_negative(
+        problems,
+        &format!("{}.velocity_per_size", name),
+        effect.velocity_per_size,
+    );
+    in_range(
+        problems,
+        &format!("{}.spread", name),
+        effect.spread,
+        0f32,
+        std::f32::consts::TAU,
+    );
+    in_range(
+        problems,
+        &format!("{}.speed_spread", name),
+        effect.speed_spread,
+        0f32,
+        1f32,
+    );
+    positive(
+        problems,
+        &format!("{}.radius_per_size", name),
+        effect.radius_per_size,
+    );
+    if let Some(end_radius_per_size) = effect.end_radius_per_size {
+        not_negative(
+            problems,
+            &format!("{}.end_radius_per_size", name),
+            end_radius_per_size,
+        );
+    }
+    for (color_name, color) in [
+        ("color", Some(effect.color)),
+        ("end_color", effect.end_color),
+    ] {
+        let Some(color) = color else {
+            continue;
+        };
+        for (channel, value) in ["red", "green", "blue", "alpha"].iter().zip(color) {
+            in_range(
+                problems,
+                &format!("{}.{} ({})", name, color_name, channel),
+                value,
+                0f32,
+                1f32,
+            );
+        }
+    }
+    for (axis, value) in ["x", "y"].iter().zip(effect.gravity) {
+        if !value.is_finite() {
+            problems.push(format!("{}.gravity ({}) has to be a number", name, axis));
+        }
+    }
+    not_negative(problems, &format!("{}.drag", name), effect.drag);
+}
+
+fn not_negative_curve(problems: &mut Vec<String>, name: &str, curve: &Curve) {
+    if curve.points.is_empty() {
+        problems.push(format!("{} needs at least one point", name));
+    }
+    for &(altitude, _) in &curve.points {
+        if !altitude.is_finite() {
+            problems.push(format!(
+                "{} altitudes have to be numbers, but one is {}",
+                name, altitude
+            ));
+        }
+    }
+    for window in curve.points.windows(2) {
+        if window[1].0 < window[0].0 {
+            problems.push(format!(
+                "{} has to be sorted by altitude, but {} comes after {}",
+                name, window[1].0, window[0].0
+            ));
+        }
+    }
+    for &(altitude, value) in &curve.points {
+        if value < 0f32 || !value.is_finite() {
+            problems.push(format!(
+                "{} can't be negative, but is {} at altitude {}",
+                name, value, altitude
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_config_is_the_default() {
+        let config = Config::parse(include_str!("../config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn rejects_zones_on_the_player_at_the_start() {
+        for text in [
+            "[zone]\nside_distance = -5.0",
+            "[zone]\nside_distance = 600.0",
+            "[zone]\nbottom_position = [0.0, 600.0]",
+        ] {
+            let error = Config::parse(text).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", text);
+        }
+        assert!(
+            Config::parse("[zone]\nside_distance = 650.0\nbottom_position = [0.0, 650.0]").is_ok()
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_numbers_or_too_small() {
+        for text in [
+            "[camera]\nmax_zoom = nan",
+            "[camera]\nmax_zoom = inf",
+            "[player]\nmax_rope_length = nan",
+            "[player]\nradius = nan",
+            "[player.particles]\ntime_per_particle = 0.00001",
+            "[player.particles]\ntime_per_particle = nan",
+            "[difficulty]\nenemies_per_chunk = [[nan, 1.0]]",
+        ] {
+            let error = Config::parse(text).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", text);
+        }
+        assert!(Config::parse("[player.particles]\ntime_per_particle = 0.0001").is_ok());
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::Result;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::config::Config;
+
+/// Seconds between two looks at the file.
+pub const CHECK_INTERVAL: f32 = 0.5;
+
+/// Watches the config file for changes while the game runs, so it can be tuned without
+/// restarting. Looks at the file's modification time every `CHECK_INTERVAL`, which is cheap
+/// enough to do from the game loop.
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    time_until_check: f32,
+}
+
+impl ConfigWatcher {
+    /// Only changes from now on count, whatever is in the file now is assumed to be loaded.
+    pub fn new(path: PathBuf) -> Self {
+        let modified = Self::modified(&path);
+        Self {
+            path,
+            modified,
+            time_until_check: CHECK_INTERVAL,
+        }
+    }
+
+    fn modified(path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// The newly loaded config if the file changed since the last time, or why it couldn't be
+    /// loaded. A broken file is only reported once, until it changes again. So is a file that
+    /// was deleted or moved away, that doesn't mean going back to the default config.
+    pub fn poll(self: &mut Self, frame_time: f32) -> Option<Result<Config>> {
+        self.time_until_check -= frame_time;
+        if self.time_until_check > 0f32 {
+            return None;
+        }
+        self.time_until_check = CHECK_INTERVAL;
+
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(fs::read_to_string(&self.path).and_then(|text| Config::parse(&text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn reports_a_deleted_file_instead_of_going_back_to_defaults() {
+        let path = std::env::temp_dir().join(format!("config_watcher_{}.toml", std::process::id()));
+        fs::write(&path, "[player]\nradius = 40.0\n").unwrap();
+        let mut watcher = ConfigWatcher::new(path.clone());
+
+        fs::remove_file(&path).unwrap();
+        let error = watcher.poll(CHECK_INTERVAL).unwrap().err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(watcher.poll(CHECK_INTERVAL).is_none());
+
+        fs::write(&path, "[player]\nradius = 30.0\n").unwrap();
+        let config = watcher.poll(CHECK_INTERVAL).unwrap().unwrap();
+        assert_eq!(config.player.radius, 30f32);
+        fs::remove_file(&path).unwrap();
+    }
+}
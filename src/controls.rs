@@ -0,0 +1,522 @@
+use std::fmt;
+
+use macroquad::input::{
+    is_key_down, is_key_pressed, is_key_released, is_mouse_button_down, is_mouse_button_pressed,
+    is_mouse_button_released, KeyCode, MouseButton,
+};
+use macroquad::math::Vec2;
+use serde::Deserialize;
+
+/// Stick movements smaller than this (0 to 1) are ignored, sticks rarely rest exactly in the
+/// middle.
+pub const STICK_DEAD_ZONE: f32 = 0.3;
+
+/// What the player can do, independent of which keys or buttons do it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Hold on to a planet.
+    Grab,
+    /// Speed up in the direction the player is flying.
+    Boost,
+    Shoot,
+    /// Pause and unpause.
+    Pause,
+    /// Shorten the rope while holding on to a planet.
+    ReelIn,
+    /// Let out more rope while holding on to a planet.
+    ReelOut,
+    /// Start a run from the title screen and try again after dying.
+    Confirm,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+const GAMEPAD_BUTTONS: &[GamepadButton] = &[
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::North,
+    GamepadButton::West,
+    GamepadButton::LeftTrigger,
+    GamepadButton::LeftTrigger2,
+    GamepadButton::RightTrigger,
+    GamepadButton::RightTrigger2,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::LeftThumb,
+    GamepadButton::RightThumb,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+const MOUSE_BUTTONS: &[MouseButton] = &[MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+// keys that can be bound, by their name in the config
+const KEYS: &[KeyCode] = &[
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::Escape,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpEnter,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightShift,
+    KeyCode::RightControl,
+    KeyCode::RightAlt,
+];
+
+// finds a key or button by its (case insensitive) name
+fn find_by_name<T: fmt::Debug + Copy>(options: &[T], name: &str) -> Option<T> {
+    options
+        .iter()
+        .find(|option| format!("{:?}", option).eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// One key or button. In the config it's the key's name (`"Space"`, `"F"`, `"LeftShift"`),
+/// `"Mouse:Left"` or a gamepad button like `"Gamepad:South"`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+// how bindings are shown on screen, like "SPACE", "MOUSE LEFT" or "GAMEPAD SOUTH"
+impl fmt::Display for Binding {
+    fn fmt(self: &Self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Gamepad {:?}", button),
+        };
+        write!(f, "{}", name.to_uppercase())
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let binding = match name.split_once(':') {
+            Some((device, button)) if device.eq_ignore_ascii_case("mouse") => {
+                find_by_name(MOUSE_BUTTONS, button).map(Binding::Mouse)
+            }
+            Some((device, button)) if device.eq_ignore_ascii_case("gamepad") => {
+                find_by_name(GAMEPAD_BUTTONS, button).map(Binding::Gamepad)
+            }
+            Some(_) => None,
+            None => find_by_name(KEYS, &name).map(Binding::Key),
+        };
+        binding.ok_or_else(|| {
+            format!(
+                "unknown key or button `{}`, expected a key like `Space`, `Mouse:Left` or \
+                 `Gamepad:South`",
+                name
+            )
+        })
+    }
+}
+
+/// Which keys and buttons do what, several can be bound to one action.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bindings {
+    pub grab: Vec<Binding>,
+    pub boost: Vec<Binding>,
+    pub shoot: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    pub reel_in: Vec<Binding>,
+    pub reel_out: Vec<Binding>,
+    pub confirm: Vec<Binding>,
+}
+
+impl Bindings {
+    pub fn of(self: &Self, action: Action) -> &[Binding] {
+        match action {
+            Action::Grab => &self.grab,
+            Action::Boost => &self.boost,
+            Action::Shoot => &self.shoot,
+            Action::Pause => &self.pause,
+            Action::ReelIn => &self.reel_in,
+            Action::ReelOut => &self.reel_out,
+            Action::Confirm => &self.confirm,
+        }
+    }
+
+    /// What `action` is bound to for showing on screen, like "ESCAPE or P". Gamepad buttons are
+    /// left out when there is no gamepad support, unless there is nothing else.
+    pub fn names(self: &Self, action: Action) -> String {
+        let bindings = self.of(action);
+        let shown: Vec<String> = bindings
+            .iter()
+            .filter(|binding| cfg!(feature = "gamepad") || !matches!(binding, Binding::Gamepad(_)))
+            .map(Binding::to_string)
+            .collect();
+        if shown.is_empty() {
+            let all: Vec<String> = bindings.iter().map(Binding::to_string).collect();
+            return all.join(" or ");
+        }
+        shown.join(" or ")
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            grab: vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Gamepad(GamepadButton::South),
+            ],
+            boost: vec![
+                Binding::Key(KeyCode::LeftShift),
+                Binding::Gamepad(GamepadButton::LeftTrigger2),
+            ],
+            shoot: vec![
+                Binding::Mouse(MouseButton::Left),
+                Binding::Key(KeyCode::F),
+                Binding::Gamepad(GamepadButton::RightTrigger2),
+            ],
+            pause: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Key(KeyCode::P),
+                Binding::Gamepad(GamepadButton::Start),
+            ],
+            reel_in: vec![
+                Binding::Key(KeyCode::W),
+                Binding::Key(KeyCode::Up),
+                Binding::Gamepad(GamepadButton::DPadUp),
+            ],
+            reel_out: vec![
+                Binding::Key(KeyCode::S),
+                Binding::Key(KeyCode::Down),
+                Binding::Gamepad(GamepadButton::DPadDown),
+            ],
+            confirm: vec![
+                Binding::Key(KeyCode::Enter),
+                Binding::Key(KeyCode::R),
+                Binding::Gamepad(GamepadButton::Start),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct ButtonState {
+    /// Went down this frame.
+    pub pressed: bool,
+    /// Went up this frame.
+    pub released: bool,
+    pub down: bool,
+}
+
+impl ButtonState {
+    fn combine(self: Self, other: ButtonState) -> ButtonState {
+        ButtonState {
+            pressed: self.pressed || other.pressed,
+            released: self.released || other.released,
+            down: self.down || other.down,
+        }
+    }
+}
+
+/// The actions for one frame, whatever they are bound to.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ActionState {
+    pub grab: ButtonState,
+    pub boost: ButtonState,
+    pub shoot: ButtonState,
+    pub pause: ButtonState,
+    pub reel_in: ButtonState,
+    pub reel_out: ButtonState,
+    pub confirm: ButtonState,
+    /// Shooting with a mouse button, so the shot goes where the mouse points.
+    pub shoot_with_mouse: bool,
+    /// Where the right stick points (x right, y down, up to length 1), zero when it's in the
+    /// dead zone or there is no gamepad.
+    pub aim_stick: Vec2,
+}
+
+/// Turns keyboard, mouse and gamepad input into actions through the bindings. Gamepads only work
+/// when the game is built with the `gamepad` feature.
+pub struct Controls {
+    pub bindings: Bindings,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<gamepad::Gamepads>,
+}
+
+impl Controls {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            #[cfg(feature = "gamepad")]
+            gamepads: gamepad::Gamepads::new(),
+        }
+    }
+
+    /// Reads this frame's input, call once per frame.
+    pub fn read(self: &mut Self) -> ActionState {
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepads) = &mut self.gamepads {
+            gamepads.update();
+        }
+        let shoot_with_mouse = self.bindings.shoot.iter().any(
+            |binding| matches!(binding, Binding::Mouse(button) if is_mouse_button_down(*button)),
+        );
+        #[cfg(feature = "gamepad")]
+        let aim_stick = self
+            .gamepads
+            .as_ref()
+            .map_or(Vec2::ZERO, |gamepads| gamepads.right_stick);
+        #[cfg(not(feature = "gamepad"))]
+        let aim_stick = Vec2::ZERO;
+        let aim_stick = if aim_stick.length() < STICK_DEAD_ZONE {
+            Vec2::ZERO
+        } else {
+            aim_stick.clamp_length_max(1f32)
+        };
+        ActionState {
+            grab: self.action(Action::Grab),
+            boost: self.action(Action::Boost),
+            shoot: self.action(Action::Shoot),
+            pause: self.action(Action::Pause),
+            reel_in: self.action(Action::ReelIn),
+            reel_out: self.action(Action::ReelOut),
+            confirm: self.action(Action::Confirm),
+            shoot_with_mouse,
+            aim_stick,
+        }
+    }
+
+    fn action(self: &Self, action: Action) -> ButtonState {
+        self.bindings
+            .of(action)
+            .iter()
+            .map(|binding| self.binding(binding))
+            .fold(ButtonState::default(), ButtonState::combine)
+    }
+
+    fn binding(self: &Self, binding: &Binding) -> ButtonState {
+        match *binding {
+            Binding::Key(key) => ButtonState {
+                pressed: is_key_pressed(key),
+                released: is_key_released(key),
+                down: is_key_down(key),
+            },
+            Binding::Mouse(button) => ButtonState {
+                pressed: is_mouse_button_pressed(button),
+                released: is_mouse_button_released(button),
+                down: is_mouse_button_down(button),
+            },
+            #[cfg(feature = "gamepad")]
+            Binding::Gamepad(button) => self
+                .gamepads
+                .as_ref()
+                .map_or(ButtonState::default(), |gamepads| gamepads.button(button)),
+            #[cfg(not(feature = "gamepad"))]
+            Binding::Gamepad(_) => ButtonState::default(),
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+mod gamepad {
+    use std::collections::HashSet;
+
+    use gilrs::{Axis, Button, EventType, Gilrs};
+    use macroquad::math::Vec2;
+
+    use super::{ButtonState, GamepadButton};
+
+    /// All connected gamepads together, a button counts as down if it is on any of them.
+    pub struct Gamepads {
+        gilrs: Gilrs,
+        down: HashSet<GamepadButton>,
+        pressed: HashSet<GamepadButton>,
+        released: HashSet<GamepadButton>,
+        pub right_stick: Vec2,
+    }
+
+    impl Gamepads {
+        pub fn new() -> Option<Self> {
+            match Gilrs::new() {
+                Ok(gilrs) => Some(Self {
+                    gilrs,
+                    down: HashSet::new(),
+                    pressed: HashSet::new(),
+                    released: HashSet::new(),
+                    right_stick: Vec2::ZERO,
+                }),
+                Err(error) => {
+                    eprintln!("no gamepad support: {}", error);
+                    None
+                }
+            }
+        }
+
+        pub fn update(self: &mut Self) {
+            self.pressed.clear();
+            self.released.clear();
+            while let Some(event) = self.gilrs.next_event() {
+                match event.event {
+                    EventType::ButtonPressed(button, _) => {
+                        if let Some(button) = from_gilrs(button) {
+                            self.pressed.insert(button);
+                            self.down.insert(button);
+                        }
+                    }
+                    EventType::ButtonReleased(button, _) => {
+                        if let Some(button) = from_gilrs(button) {
+                            self.released.insert(button);
+                            self.down.remove(&button);
+                        }
+                    }
+                    EventType::AxisChanged(Axis::RightStickX, value, _) => {
+                        self.right_stick.x = value;
+                    }
+                    // gilrs has y going up, the screen down
+                    EventType::AxisChanged(Axis::RightStickY, value, _) => {
+                        self.right_stick.y = -value;
+                    }
+                    // let go of everything, so nothing stays stuck down
+                    EventType::Disconnected => {
+                        self.released.extend(self.down.drain());
+                        self.right_stick = Vec2::ZERO;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        pub fn button(self: &Self, button: GamepadButton) -> ButtonState {
+            ButtonState {
+                pressed: self.pressed.contains(&button),
+                released: self.released.contains(&button),
+                down: self.down.contains(&button),
+            }
+        }
+    }
+
+    fn from_gilrs(button: Button) -> Option<GamepadButton> {
+        Some(match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::North => GamepadButton::North,
+            Button::West => GamepadButton::West,
+            Button::LeftTrigger => GamepadButton::LeftTrigger,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger2,
+            Button::RightTrigger => GamepadButton::RightTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger2,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::LeftThumb => GamepadButton::LeftThumb,
+            Button::RightThumb => GamepadButton::RightThumb,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+}
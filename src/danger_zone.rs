@@ -1,73 +1,132 @@
+use crate::particle::ParticleGroup;
 use crate::particle_controller::ParticleController;
+use crate::particle_effect::{ParticleEffect, SpawnShape};
+use crate::particle_pool::ParticlePool;
 use crate::player::Player;
 use macroquad::math::Vec2;
-use macroquad::{camera::Camera2D, color::Color};
+use serde::Deserialize;
+
+/// Particles of a zone that caught the player live this long, so it slowly fills up with them.
+pub const CAUGHT_PARTICLE_LIFESPAN: f32 = 20f32;
+/// How fast a zone that caught the player moves up, to swallow them.
+pub const CAUGHT_SPEED: f32 = 600f32;
+
+/// Where the danger zones start and how they look. Positions are relative to the player's start.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ZoneSettings {
+    /// Center of the bottom zone.
+    pub bottom_position: [f32; 2],
+    pub bottom_size: [f32; 2],
+    /// Speed the bottom zone starts rising with, it speeds up with the difficulty's
+    /// `bottom_zone_acceleration`.
+    pub bottom_speed: f32,
+    /// Horizontal distance of the side zones' centers from the middle.
+    pub side_distance: f32,
+    pub side_size: [f32; 2],
+    /// Particle speed and radius are relative to the zone's longest side, `SpawnShape::Box`
+    /// fills the whole zone.
+    pub bottom_particles: ParticleEffect,
+    pub side_particles: ParticleEffect,
+}
+
+impl Default for ZoneSettings {
+    fn default() -> Self {
+        let particles = ParticleEffect {
+            time_per_particle: 0.01,
+            lifespan: 3f32,
+            shape: SpawnShape::Box,
+            velocity_per_size: 0.03,
+            radius_per_size: 0.03,
+            color: [0.8, 0.1, 0.1, 0.7],
+            end_color: Some([0.0, 0.1, 0.1, 0.7]),
+            ..ParticleEffect::default()
+        };
+        Self {
+            bottom_position: [0f32, 1000f32],
+            bottom_size: [3000f32, 1200f32],
+            bottom_speed: 20f32,
+            side_distance: 1600f32,
+            side_size: [1200f32, 2000f32],
+            bottom_particles: ParticleEffect {
+                time_per_particle: 0.003,
+                ..particles
+            },
+            side_particles: particles,
+        }
+    }
+}
 
 pub struct DangerZone {
     pub position: Vec2,
+    pub previous_position: Vec2,
     pub size: Vec2,
     pub velocity: Vec2,
     pub particle_controller: ParticleController,
+    pub has_caught_player: bool,
 }
 
-const ZONE_COLOR: Color = Color {
-    r: 0.8,
-    g: 0.1,
-    b: 0.1,
-    a: 0.7,
-};
-
 impl DangerZone {
-    pub fn new(position: Vec2, size: Vec2, velocity: Vec2, time_per_particle: f32) -> Self {
-        return DangerZone {
-            position: position,
-            size: size,
-            velocity: velocity,
-            particle_controller: ParticleController::new(
-                time_per_particle,
-                size.max_element() * 0.03,
-                size.max_element() * 0.03,
-                ZONE_COLOR,
-                3.0,
-            ),
-        };
+    pub fn new(
+        position: Vec2,
+        size: Vec2,
+        velocity: Vec2,
+        particles: &ParticleEffect,
+        group: ParticleGroup,
+    ) -> Self {
+        let mut particle_controller = particles.controller(size.max_element(), group);
+        particle_controller.area = size;
+        DangerZone {
+            position,
+            previous_position: position,
+            size,
+            velocity,
+            particle_controller,
+            has_caught_player: false,
+        }
     }
 
-    pub fn update(self: &mut Self, delta_time: f32) {
-        self.position.y += self.velocity.y * delta_time;
+    /// Switches to new tuning in the middle of a run, the zone stays where it is. A zone that
+    /// caught the player keeps its longer lived particles.
+    pub fn apply_settings(self: &mut Self, size: Vec2, particles: &ParticleEffect) {
+        self.size = size;
+        particles.apply_to(&mut self.particle_controller, size.max_element());
+        self.particle_controller.area = size;
+        if self.has_caught_player {
+            self.particle_controller.effect.lifespan = CAUGHT_PARTICLE_LIFESPAN;
+        }
+    }
 
-        self.particle_controller.update_with_range(
-            delta_time,
-            Vec2 {
-                x: self.position.x - self.size.x * 0.5,
-                y: self.position.y - self.size.y * 0.5,
-            },
-            Vec2 {
-                x: self.position.x + self.size.x * 0.5,
-                y: self.position.y + self.size.y * 0.5,
-            },
-        );
+    pub fn update(self: &mut Self, particles: &mut ParticlePool, delta_time: f32) {
+        self.position.y += self.velocity.y * delta_time;
 
         self.particle_controller
-            .shift_color(-0.4 * delta_time, 0f32, 0f32, 0f32);
+            .update(particles, delta_time, self.position);
     }
 
-    pub fn update_as_bottom_zone(self: &mut Self, delta_time: f32, position_x: f32) {
+    pub fn update_as_bottom_zone(
+        self: &mut Self,
+        particles: &mut ParticlePool,
+        delta_time: f32,
+        position_x: f32,
+        acceleration: f32,
+    ) {
         self.position.x = position_x;
-        self.velocity.y *= 1f32 + 0.2 * delta_time;
+        self.velocity.y *= 1f32 + acceleration * delta_time;
         // if self.size.y > self.size.max_element() * 0.03 {
         //     self.position.y -= self.size.y + 90000f32 / self.velocity.y;
         //     self.size.y = 90000f32 / self.velocity.y.abs();
         // }
-        self.update(delta_time);
+        self.update(particles, delta_time);
     }
-    pub fn update_as_side_zone(self: &mut Self, delta_time: f32, position_y: f32) {
+    pub fn update_as_side_zone(
+        self: &mut Self,
+        particles: &mut ParticlePool,
+        delta_time: f32,
+        position_y: f32,
+    ) {
         self.position.y = position_y;
-        self.update(delta_time);
-    }
-
-    pub fn draw(self: &Self, camera: &Camera2D) {
-        self.particle_controller.draw(camera);
+        self.update(particles, delta_time);
     }
 
     pub fn check_and_handle_player_collision(self: &mut Self, player: &mut Player) {
@@ -77,8 +136,9 @@ impl DangerZone {
             && self.position.y - self.size.y * 0.5 < player.position.y + player.radius
         {
             player.is_dead = true;
-            self.particle_controller.lifespan = 20f32;
-            self.velocity.y = -600f32;
+            self.has_caught_player = true;
+            self.particle_controller.effect.lifespan = CAUGHT_PARTICLE_LIFESPAN;
+            self.velocity.y = -CAUGHT_SPEED;
         }
     }
 }
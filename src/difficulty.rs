@@ -0,0 +1,121 @@
+use serde::Deserialize;
+
+/// A value that changes with altitude, given as points of (altitude, value). Between points the
+/// value is interpolated linearly, outside of them it stays at the first or last value. In the
+/// config file it's just the list of points, like `[[0, 2.5], [20000, 1.8]]`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Curve {
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    /// `points` have to be sorted by altitude.
+    pub fn new(points: Vec<(f32, f32)>) -> Self {
+        Self { points }
+    }
+
+    pub fn constant(value: f32) -> Self {
+        Self::new(vec![(0f32, value)])
+    }
+
+    pub fn value_at(self: &Self, altitude: f32) -> f32 {
+        let Some(&(first_altitude, first_value)) = self.points.first() else {
+            return 0f32;
+        };
+        if altitude <= first_altitude {
+            return first_value;
+        }
+        for window in self.points.windows(2) {
+            let (from_altitude, from_value) = window[0];
+            let (to_altitude, to_value) = window[1];
+            if altitude <= to_altitude {
+                let t = (altitude - from_altitude) / (to_altitude - from_altitude);
+                return from_value + (to_value - from_value) * t;
+            }
+        }
+        self.points[self.points.len() - 1].1
+    }
+}
+
+/// How hard the game is at each altitude. Altitude is how far above the start something is,
+/// so `-position.y`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DifficultyProfile {
+    /// Average number of planets in a chunk of the planet field.
+    pub planets_per_chunk: Curve,
+    pub min_planet_radius: Curve,
+    pub max_planet_radius: Curve,
+    /// Planets drift with up to this speed on each axis.
+    pub max_planet_drift: Curve,
+    /// How much faster (relative) the bottom danger zone gets each second.
+    pub bottom_zone_acceleration: Curve,
+    /// Average number of enemies in a chunk, none until the player got the hang of it.
+    pub enemies_per_chunk: Curve,
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        Self {
+            planets_per_chunk: Curve::new(vec![(0f32, 2.5), (20000f32, 1.8), (60000f32, 1.2)]),
+            min_planet_radius: Curve::constant(10f32),
+            max_planet_radius: Curve::new(vec![
+                (0f32, 100f32),
+                (30000f32, 70f32),
+                (60000f32, 50f32),
+            ]),
+            max_planet_drift: Curve::new(vec![
+                (0f32, 30f32),
+                (30000f32, 60f32),
+                (60000f32, 100f32),
+            ]),
+            bottom_zone_acceleration: Curve::new(vec![(0f32, 0.2), (60000f32, 0.3)]),
+            enemies_per_chunk: Curve::new(vec![(3000f32, 0f32), (10000f32, 0.5), (40000f32, 1.5)]),
+        }
+    }
+}
+
+pub fn altitude(y: f32) -> f32 {
+    -y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_points() {
+        let curve = Curve::new(vec![(0f32, 2f32), (100f32, 4f32), (300f32, 0f32)]);
+        assert_eq!(curve.value_at(0f32), 2f32);
+        assert_eq!(curve.value_at(50f32), 3f32);
+        assert_eq!(curve.value_at(100f32), 4f32);
+        assert_eq!(curve.value_at(200f32), 2f32);
+        assert_eq!(curve.value_at(300f32), 0f32);
+    }
+
+    #[test]
+    fn stays_at_the_first_and_last_value_outside_the_points() {
+        let curve = Curve::new(vec![(1000f32, 5f32), (2000f32, 1f32)]);
+        assert_eq!(curve.value_at(-500f32), 5f32);
+        assert_eq!(curve.value_at(999f32), 5f32);
+        assert_eq!(curve.value_at(2001f32), 1f32);
+        assert_eq!(curve.value_at(1e9), 1f32);
+    }
+
+    #[test]
+    fn a_single_point_is_the_same_everywhere() {
+        let curve = Curve::constant(1.5);
+        for altitude in [-1000f32, 0f32, 1000f32] {
+            assert_eq!(curve.value_at(altitude), 1.5);
+        }
+        assert_eq!(Curve::new(Vec::new()).value_at(10f32), 0f32);
+    }
+
+    #[test]
+    fn two_points_at_the_same_altitude_are_a_step() {
+        let curve = Curve::new(vec![(0f32, 1f32), (100f32, 1f32), (100f32, 3f32)]);
+        assert_eq!(curve.value_at(100f32), 1f32);
+        assert_eq!(curve.value_at(100.5), 3f32);
+    }
+}
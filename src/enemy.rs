@@ -0,0 +1,232 @@
+use macroquad::math::Vec2;
+use serde::Deserialize;
+
+use crate::particle::ParticleGroup;
+use crate::particle_controller::ParticleController;
+use crate::particle_effect::ParticleEffect;
+use crate::particle_pool::ParticlePool;
+use crate::planet::Planet;
+use crate::player::Player;
+use crate::projectile::Projectile;
+
+/// Distance from the planet's surface an orbiter keeps.
+const ORBIT_DISTANCE: f32 = 120f32;
+/// Orbiters dodge projectiles coming closer than this.
+const DODGE_DISTANCE: f32 = 250f32;
+
+/// What enemies look like, they are only particle clouds. Particle speed and radius are
+/// relative to the enemy's radius.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnemySettings {
+    pub chaser_particles: ParticleEffect,
+    pub orbiter_particles: ParticleEffect,
+    pub mine_particles: ParticleEffect,
+}
+
+impl EnemySettings {
+    pub fn particles(self: &Self, kind: EnemyKind) -> &ParticleEffect {
+        match kind {
+            EnemyKind::Chaser => &self.chaser_particles,
+            EnemyKind::Orbiter { .. } => &self.orbiter_particles,
+            EnemyKind::Mine => &self.mine_particles,
+        }
+    }
+}
+
+impl Default for EnemySettings {
+    fn default() -> Self {
+        let particles = ParticleEffect {
+            lifespan: 0.4,
+            ..ParticleEffect::default()
+        };
+        Self {
+            chaser_particles: ParticleEffect {
+                color: [0.8, 0.1, 0.7, 0.3],
+                ..particles
+            },
+            orbiter_particles: ParticleEffect {
+                color: [0.1, 0.7, 0.9, 0.3],
+                ..particles
+            },
+            mine_particles: ParticleEffect {
+                color: [1.0, 0.4, 0.0, 0.3],
+                ..particles
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyKind {
+    /// Flies straight at the player.
+    Chaser,
+    /// Circles a planet (by index into the planet list) and dodges projectiles. Turns into a
+    /// chaser once its planet is gone.
+    Orbiter { planet_index: usize },
+    /// Drifts along and doesn't care about anything.
+    Mine,
+}
+
+pub struct Enemy {
+    pub kind: EnemyKind,
+    pub position: Vec2,
+    pub previous_position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f32,
+    pub health: u32,
+    pub max_speed: f32,
+    /// How quickly the enemy can change its velocity (per second).
+    pub max_force: f32,
+    pub particle_controller: ParticleController,
+}
+
+/// Steering force that turns `velocity` toward going full speed at `target`.
+pub fn seek(position: Vec2, velocity: Vec2, target: Vec2, max_speed: f32) -> Vec2 {
+    (target - position).normalize_or_zero() * max_speed - velocity
+}
+
+/// Steering force that turns `velocity` toward going full speed away from `threat`.
+pub fn flee(position: Vec2, velocity: Vec2, threat: Vec2, max_speed: f32) -> Vec2 {
+    (position - threat).normalize_or_zero() * max_speed - velocity
+}
+
+/// Steering force that turns `velocity` toward circling `center` at `orbit_radius`
+/// (clockwise on screen).
+pub fn orbit(
+    position: Vec2,
+    velocity: Vec2,
+    center: Vec2,
+    orbit_radius: f32,
+    max_speed: f32,
+) -> Vec2 {
+    let from_center = position - center;
+    let distance = from_center.length();
+    let outward = from_center.normalize_or(Vec2::X);
+    let tangent = outward.perp();
+    // move along the circle and back onto it when drifting off
+    let desired = tangent * max_speed + outward * (orbit_radius - distance);
+    desired.clamp_length_max(max_speed) - velocity
+}
+
+impl Enemy {
+    fn new(kind: EnemyKind, position: Vec2, velocity: Vec2, settings: &EnemySettings) -> Self {
+        // radius, health, max speed, max force
+        let (radius, health, max_speed, max_force) = match kind {
+            EnemyKind::Chaser => (25f32, 2, 350f32, 400f32),
+            EnemyKind::Orbiter { .. } => (20f32, 1, 300f32, 900f32),
+            EnemyKind::Mine => (30f32, 1, velocity.length(), 0f32),
+        };
+        Self {
+            kind,
+            position,
+            previous_position: position,
+            velocity,
+            radius,
+            health,
+            max_speed,
+            max_force,
+            particle_controller: settings
+                .particles(kind)
+                .controller(radius, ParticleGroup::Enemy),
+        }
+    }
+
+    pub fn new_chaser(position: Vec2, settings: &EnemySettings) -> Self {
+        Self::new(EnemyKind::Chaser, position, Vec2::ZERO, settings)
+    }
+
+    pub fn new_orbiter(position: Vec2, planet_index: usize, settings: &EnemySettings) -> Self {
+        Self::new(
+            EnemyKind::Orbiter { planet_index },
+            position,
+            Vec2::ZERO,
+            settings,
+        )
+    }
+
+    pub fn new_mine(position: Vec2, velocity: Vec2, settings: &EnemySettings) -> Self {
+        Self::new(EnemyKind::Mine, position, velocity, settings)
+    }
+
+    /// Switches to a new look in the middle of a run. An orbiter that became a chaser keeps
+    /// looking like a chaser.
+    pub fn apply_settings(self: &mut Self, settings: &EnemySettings) {
+        settings
+            .particles(self.kind)
+            .apply_to(&mut self.particle_controller, self.radius);
+    }
+
+    pub fn update(
+        self: &mut Self,
+        player: &Player,
+        planets: &[Planet],
+        projectiles: &[Projectile],
+        particles: &mut ParticlePool,
+        delta_time: f32,
+    ) {
+        let steering = match self.kind {
+            EnemyKind::Chaser => seek(
+                self.position,
+                self.velocity,
+                player.position,
+                self.max_speed,
+            ),
+            EnemyKind::Orbiter { planet_index } => {
+                let threat = projectiles
+                    .iter()
+                    .find(|projectile| {
+                        (projectile.position - self.position).length() < DODGE_DISTANCE
+                    })
+                    .map(|projectile| projectile.position);
+                match threat {
+                    Some(threat) => flee(self.position, self.velocity, threat, self.max_speed),
+                    None => {
+                        let planet = &planets[planet_index];
+                        orbit(
+                            self.position,
+                            self.velocity,
+                            planet.position,
+                            planet.radius + ORBIT_DISTANCE,
+                            self.max_speed,
+                        )
+                    }
+                }
+            }
+            EnemyKind::Mine => Vec2::ZERO,
+        };
+
+        self.velocity += steering.clamp_length_max(self.max_force * delta_time);
+        self.velocity = self.velocity.clamp_length_max(self.max_speed);
+
+        self.particle_controller
+            .update(particles, delta_time, self.position);
+        let change = self.velocity * delta_time;
+        self.position += change;
+        self.particle_controller.inherit_movement(particles, change);
+    }
+
+    /// Enemies are deadly to the touch.
+    pub fn check_and_handle_player_collision(self: &Self, player: &mut Player) {
+        if (self.position - player.position).length() < self.radius + player.radius {
+            player.is_dead = true;
+        }
+    }
+
+    /// Takes a hit, returns true if that destroyed the enemy.
+    pub fn hit(self: &mut Self) -> bool {
+        self.health = self.health.saturating_sub(1);
+        self.is_destroyed()
+    }
+
+    /// Orbiters lose their planet when it's despawned, from then on they go after the player.
+    pub fn lose_planet(self: &mut Self) {
+        if let EnemyKind::Orbiter { .. } = self.kind {
+            self.kind = EnemyKind::Chaser;
+        }
+    }
+
+    pub fn is_destroyed(self: &Self) -> bool {
+        self.health == 0
+    }
+}
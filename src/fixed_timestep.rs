@@ -0,0 +1,38 @@
+/// Turns variable frame times into a whole number of constant simulation steps, so the
+/// simulation doesn't depend on the frame rate. The time that is left over is exposed as
+/// `alpha` for interpolating between the previous and the current simulation state.
+pub struct FixedTimestep {
+    pub delta_time: f32,
+    pub max_frame_time: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(delta_time: f32) -> Self {
+        Self {
+            delta_time,
+            // don't try to catch up on more than this in one frame (e.g. after dragging the window)
+            max_frame_time: 0.25,
+            accumulator: 0f32,
+        }
+    }
+
+    pub fn accumulate(self: &mut Self, frame_time: f32) {
+        self.accumulator += frame_time.min(self.max_frame_time);
+    }
+
+    /// Returns true (and consumes one step worth of time) while a simulation step is due.
+    pub fn next_step(self: &mut Self) -> bool {
+        if self.accumulator >= self.delta_time {
+            self.accumulator -= self.delta_time;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How far the rendered frame is between the last two simulation steps (0 to 1).
+    pub fn alpha(self: &Self) -> f32 {
+        self.accumulator / self.delta_time
+    }
+}
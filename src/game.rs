@@ -0,0 +1,383 @@
+use std::path::{Path, PathBuf};
+
+use macroquad::input::{get_char_pressed, is_key_pressed, mouse_position, KeyCode};
+use macroquad::math::Vec2;
+use macroquad::models::Mesh;
+use macroquad::time::get_frame_time;
+
+use crate::assets::Assets;
+use crate::camera::GameCamera;
+use crate::config::Config;
+use crate::config_watcher::ConfigWatcher;
+use crate::controls::{ActionState, Controls};
+use crate::fixed_timestep::FixedTimestep;
+use crate::high_scores::{HighScoreTable, MAX_NAME_LENGTH};
+use crate::input::Input;
+use crate::player::GrabMode;
+use crate::random_generator::get_time_seed;
+use crate::render::{
+    draw_game_over_screen, draw_hud, draw_pause_screen, draw_title_screen, draw_world,
+    particle_mesh,
+};
+use crate::replay::Replay;
+use crate::world::{World, FIXED_DELTA_TIME};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+impl GameState {
+    /// Whether the world keeps moving. It does after dying too, so the danger zone can finish
+    /// eating the player.
+    pub fn is_simulating(self: &Self) -> bool {
+        matches!(self, GameState::Playing | GameState::GameOver)
+    }
+}
+
+/// One session of the game: the current run's world, the state it's in and everything needed to
+/// start the next run. Reads the keyboard and draws, so this is the part that needs a window.
+pub struct Game {
+    pub state: GameState,
+    pub world: World,
+    pub camera: GameCamera,
+    pub timestep: FixedTimestep,
+    /// The current run, recorded as it's played or being played back. Restarting starts a new
+    /// recording, only the last run is kept.
+    pub replay: Replay,
+    /// Every run uses this seed if set, otherwise each one gets a new one.
+    pub seed: Option<u64>,
+    pub gravity_enabled: bool,
+    /// Tuning for every run, see `with_config`.
+    pub config: Config,
+    /// Reloads `config` when its file changes, see `with_config_watcher`.
+    pub config_watcher: Option<ConfigWatcher>,
+    pub controls: Controls,
+    /// This frame's actions, read once at the start of `update`.
+    actions: ActionState,
+    pub high_scores: HighScoreTable,
+    /// Where `high_scores` is saved, None keeps them only until the game is closed.
+    pub high_scores_path: Option<PathBuf>,
+    /// The name typed so far, while a new high score is waiting to be put into the table.
+    pub name_entry: Option<String>,
+    /// Name of the last high score, suggested for the next one.
+    pub last_name: String,
+    /// Sprites to draw with, without any everything is drawn with particles.
+    pub assets: Assets,
+    // particles are drawn into this every frame, made once the particle texture is loaded
+    particle_mesh: Option<Mesh>,
+    is_replaying: bool,
+    replay_frame_index: usize,
+    input: Input,
+}
+
+impl Game {
+    pub fn new(seed: Option<u64>, gravity_enabled: bool) -> Self {
+        let config = Config::default();
+        let replay = Replay::new(seed.unwrap_or_else(get_time_seed), &config, gravity_enabled);
+        Self {
+            state: GameState::Title,
+            world: Self::build_world(&replay, &config),
+            camera: GameCamera::new(config.camera),
+            timestep: FixedTimestep::new(FIXED_DELTA_TIME),
+            replay,
+            seed,
+            gravity_enabled,
+            controls: Controls::new(config.controls.clone()),
+            config,
+            config_watcher: None,
+            actions: ActionState::default(),
+            high_scores: HighScoreTable::default(),
+            high_scores_path: None,
+            name_entry: None,
+            last_name: String::new(),
+            assets: Assets::default(),
+            particle_mesh: None,
+            is_replaying: false,
+            replay_frame_index: 0,
+            input: Input::default(),
+        }
+    }
+
+    /// Plays `replay` back instead of reading the keyboard. Restarting plays it again.
+    pub fn from_replay(replay: Replay) -> Self {
+        let config = Config::default();
+        Self {
+            state: GameState::Playing,
+            world: Self::build_world(&replay, &config),
+            camera: GameCamera::new(config.camera),
+            timestep: FixedTimestep::new(FIXED_DELTA_TIME),
+            seed: Some(replay.seed),
+            gravity_enabled: replay.gravity_enabled,
+            controls: Controls::new(config.controls.clone()),
+            config,
+            config_watcher: None,
+            actions: ActionState::default(),
+            replay,
+            high_scores: HighScoreTable::default(),
+            high_scores_path: None,
+            name_entry: None,
+            last_name: String::new(),
+            assets: Assets::default(),
+            particle_mesh: None,
+            is_replaying: true,
+            replay_frame_index: 0,
+            input: Input::default(),
+        }
+    }
+
+    /// Loads the high score table from `path` and saves new high scores there.
+    pub fn with_high_scores(mut self: Self, path: PathBuf) -> Self {
+        self.high_scores = HighScoreTable::load(&path).unwrap_or_else(|error| {
+            eprintln!(
+                "couldn't load high scores from {}: {}",
+                path.display(),
+                error
+            );
+            HighScoreTable::default()
+        });
+        self.high_scores_path = Some(path);
+        self
+    }
+
+    /// Keeps applying the config file at `path` to the running game whenever it's saved. Changes
+    /// made while recording won't be in the replay, so it won't play back the same.
+    pub fn with_config_watcher(mut self: Self, path: PathBuf) -> Self {
+        self.config_watcher = Some(ConfigWatcher::new(path));
+        self
+    }
+
+    /// Loads sprites from `path`, whatever isn't there keeps being drawn with particles.
+    pub fn with_assets(mut self: Self, path: &Path) -> Self {
+        self.assets = Assets::load(path);
+        self.particle_mesh = self.assets.particle_texture.as_ref().map(particle_mesh);
+        self
+    }
+
+    /// Uses `config` from now on, starting with a new world built with it. A replay has to be
+    /// played back with the config it was recorded with, see `Replay::check_config`.
+    pub fn with_config(mut self: Self, config: Config) -> Self {
+        if !self.is_replaying {
+            self.replay.config_hash = config.simulation_hash();
+        }
+        self.world = Self::build_world(&self.replay, &config);
+        self.camera = GameCamera::new(config.camera);
+        self.controls.bindings = config.controls.clone();
+        self.config = config;
+        self
+    }
+
+    fn build_world(replay: &Replay, config: &Config) -> World {
+        let mut world = World::new(replay.seed, config);
+        world.gravity_enabled = replay.gravity_enabled;
+        world
+    }
+
+    /// Starts a new run with a fresh player, planets and danger zones.
+    pub fn restart(self: &mut Self) {
+        if !self.is_replaying {
+            self.replay = Replay::new(
+                self.seed.unwrap_or_else(get_time_seed),
+                &self.config,
+                self.gravity_enabled,
+            );
+        }
+        self.world = Self::build_world(&self.replay, &self.config);
+        self.camera = GameCamera::new(self.config.camera);
+        self.timestep = FixedTimestep::new(FIXED_DELTA_TIME);
+        self.replay_frame_index = 0;
+        self.input = Input::default();
+        self.name_entry = None;
+        self.state = GameState::Playing;
+    }
+
+    pub fn update(self: &mut Self) {
+        self.reload_config();
+        self.actions = self.controls.read();
+
+        match self.state {
+            GameState::Title => {
+                if self.actions.grab.pressed || self.actions.confirm.pressed {
+                    self.state = GameState::Playing;
+                }
+            }
+            GameState::Playing => {
+                if self.actions.pause.pressed {
+                    self.state = GameState::Paused;
+                }
+            }
+            GameState::Paused => {
+                if self.actions.pause.pressed {
+                    self.state = GameState::Playing;
+                }
+            }
+            GameState::GameOver => {
+                if self.name_entry.is_some() {
+                    self.update_name_entry();
+                } else if self.actions.confirm.pressed {
+                    self.restart();
+                    // with grab bound to the same button, the new run shouldn't start grabbing
+                    self.actions.grab.pressed = false;
+                }
+            }
+        }
+
+        if self.state.is_simulating() {
+            self.advance();
+        }
+        if self.state == GameState::Playing && self.world.player.is_dead {
+            self.state = GameState::GameOver;
+            // playing back someone else's run shouldn't end up in your table
+            if !self.is_replaying && self.high_scores.qualifies(self.world.score.total()) {
+                // throw away everything typed while playing
+                while get_char_pressed().is_some() {}
+                self.name_entry = Some(self.last_name.clone());
+            }
+        }
+
+        self.camera
+            .follow(&self.world.player, self.timestep.alpha(), get_frame_time());
+    }
+
+    fn reload_config(self: &mut Self) {
+        let Some(watcher) = &mut self.config_watcher else {
+            return;
+        };
+        match watcher.poll(get_frame_time()) {
+            Some(Ok(config)) => {
+                self.world.apply_config(&config);
+                self.camera.settings = config.camera;
+                self.controls.bindings = config.controls.clone();
+                self.config = config;
+            }
+            Some(Err(error)) => eprintln!(
+                "couldn't reload config {}, keeping the old one:\n{}",
+                watcher.path.display(),
+                error
+            ),
+            None => {}
+        }
+    }
+
+    fn update_name_entry(self: &mut Self) {
+        let Some(name) = &mut self.name_entry else {
+            return;
+        };
+        while let Some(character) = get_char_pressed() {
+            if (character.is_alphanumeric() || character == ' ' || character == '-')
+                && name.chars().count() < MAX_NAME_LENGTH
+            {
+                name.push(character);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            name.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            let name = self.name_entry.take().unwrap_or_default();
+            let name = if name.trim().is_empty() {
+                "anonymous".to_string()
+            } else {
+                name.trim().to_string()
+            };
+            self.last_name = name.clone();
+            self.high_scores.insert(name, self.world.score.total());
+            if let Some(path) = &self.high_scores_path {
+                if let Err(error) = self.high_scores.save(path) {
+                    eprintln!("couldn't save high scores to {}: {}", path.display(), error);
+                }
+            }
+        }
+    }
+
+    // reads (or plays back) this frame's input and steps the world as often as is due
+    fn advance(self: &mut Self) {
+        let (frame_time, frame_input) = if self.is_replaying {
+            match self.replay.frames.get(self.replay_frame_index) {
+                Some(frame) => {
+                    self.replay_frame_index += 1;
+                    (frame.frame_time, frame.input)
+                }
+                // the replay is over, keep showing where it ended
+                None => (0f32, Input::default()),
+            }
+        } else {
+            let actions = &self.actions;
+            let player = &self.world.player;
+            // the stick aims as far as grabbing reaches. Without it, the mouse aims for aimed
+            // grabs and mouse shots, and keys and buttons shoot straight ahead
+            let aim = if actions.aim_stick != Vec2::ZERO {
+                Some(player.position + actions.aim_stick * player.settings.max_grab_range)
+            } else if actions.shoot_with_mouse || player.settings.grab_mode == GrabMode::Aimed {
+                Some(self.camera.screen_to_world(mouse_position().into()))
+            } else {
+                None
+            };
+            let frame_input = Input {
+                grab_pressed: actions.grab.pressed,
+                grab_released: actions.grab.released,
+                grab_down: actions.grab.down,
+                boost_down: actions.boost.down,
+                reel_in: actions.reel_in.down,
+                reel_out: actions.reel_out.down,
+                shoot_down: actions.shoot.down,
+                aim,
+            };
+            self.replay.record(get_frame_time(), frame_input);
+            (get_frame_time(), frame_input)
+        };
+
+        self.timestep.accumulate(frame_time);
+
+        // presses and releases are kept until a step has seen them, even if this frame is too
+        // short for a step
+        self.input.grab_pressed |= frame_input.grab_pressed;
+        self.input.grab_released |= frame_input.grab_released;
+        self.input.grab_down = frame_input.grab_down;
+        self.input.boost_down = frame_input.boost_down;
+        self.input.reel_in = frame_input.reel_in;
+        self.input.reel_out = frame_input.reel_out;
+        self.input.shoot_down = frame_input.shoot_down;
+        self.input.aim = frame_input.aim;
+
+        // emitters off screen spawn fewer particles, the camera moves after this but not far
+        self.world.particles.view = Some(self.camera.visible_area());
+        while self.timestep.next_step() {
+            self.world.step(&self.input, self.timestep.delta_time);
+            self.input.grab_pressed = false;
+            self.input.grab_released = false;
+        }
+    }
+
+    pub fn draw(self: &mut Self) {
+        draw_world(
+            &self.world,
+            self.timestep.alpha(),
+            &self.camera,
+            &self.assets,
+            &self.config.render,
+            self.particle_mesh.as_mut(),
+        );
+        let score = self.world.score.total();
+        match self.state {
+            GameState::Title => draw_title_screen(
+                &self.controls.bindings,
+                self.world.player.settings.grab_mode,
+            ),
+            GameState::Playing => draw_hud(score, self.high_scores.best(), self.world.player.ammo),
+            GameState::Paused => {
+                draw_hud(score, self.high_scores.best(), self.world.player.ammo);
+                draw_pause_screen(&self.controls.bindings);
+            }
+            GameState::GameOver => draw_game_over_screen(
+                score,
+                &self.high_scores,
+                self.name_entry.as_deref(),
+                &self.controls.bindings,
+            ),
+        }
+    }
+}
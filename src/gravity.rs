@@ -0,0 +1,40 @@
+use macroquad::math::Vec2;
+
+use crate::planet::Planet;
+use crate::player::Player;
+
+pub const GRAVITATIONAL_CONSTANT: f32 = 2000f32;
+
+// keeps the pull finite when two bodies (almost) overlap
+const SOFTENING: f32 = 10f32;
+
+/// Acceleration a body at `position` gets from a body with `mass` at `attractor_position`.
+pub fn gravitational_acceleration(position: Vec2, attractor_position: Vec2, mass: f32) -> Vec2 {
+    let offset = attractor_position - position;
+    let distance_squared = offset.length_squared() + SOFTENING * SOFTENING;
+    offset * (GRAVITATIONAL_CONSTANT * mass / (distance_squared * distance_squared.sqrt()))
+}
+
+/// Planets pull on each other and on the player. The player is too light to pull back.
+pub fn apply_gravity(planets: &mut [Planet], player: &mut Player, delta_time: f32) {
+    for i in 0..planets.len() {
+        let (left, right) = planets.split_at_mut(i + 1);
+        let planet = &mut left[i];
+        for other in right.iter_mut() {
+            let offset = other.position - planet.position;
+            let distance_squared = offset.length_squared() + SOFTENING * SOFTENING;
+            let pull =
+                offset * (GRAVITATIONAL_CONSTANT / (distance_squared * distance_squared.sqrt()));
+            planet.velocity += pull * other.mass * delta_time;
+            other.velocity -= pull * planet.mass * delta_time;
+        }
+    }
+
+    if player.is_dead {
+        return;
+    }
+    for planet in planets.iter() {
+        player.velocity +=
+            gravitational_acceleration(player.position, planet.position, planet.mass) * delta_time;
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// How many scores the table keeps.
+pub const MAX_ENTRIES: usize = 10;
+/// Longest name that can be entered.
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+}
+
+/// The best scores, highest first, stored as one `score<TAB>name` line per entry.
+#[derive(Default)]
+pub struct HighScoreTable {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScoreTable {
+    /// Where the table is kept, in the user's data directory (if there is one).
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|data_dir| data_dir.join("davitron").join("high_scores.txt"))
+    }
+
+    /// A missing file is an empty table, lines that can't be read are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+        let mut table = Self::default();
+        for line in text.lines() {
+            let Some((score, name)) = line.split_once('\t') else {
+                continue;
+            };
+            let Ok(score) = score.parse() else {
+                continue;
+            };
+            table.insert(name.to_string(), score);
+        }
+        Ok(table)
+    }
+
+    pub fn save(self: &Self, path: &Path) -> Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&format!("{}\t{}\n", entry.score, entry.name));
+        }
+        fs::write(path, text)
+    }
+
+    pub fn best(self: &Self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    /// Whether `score` would make it into the table.
+    pub fn qualifies(self: &Self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_some_and(|entry| score > entry.score))
+    }
+
+    /// Adds the score in its place and returns that place, or None if it didn't make the cut.
+    pub fn insert(self: &mut Self, name: String, score: u32) -> Option<usize> {
+        // ties go below the older scores
+        let place = self.entries.partition_point(|entry| entry.score >= score);
+        if place >= MAX_ENTRIES {
+            return None;
+        }
+        let name: String = name.chars().take(MAX_NAME_LENGTH).collect();
+        self.entries.insert(place, HighScore { name, score });
+        self.entries.truncate(MAX_ENTRIES);
+        Some(place)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores(table: &HighScoreTable) -> Vec<u32> {
+        table.entries.iter().map(|entry| entry.score).collect()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("high_scores_{}_{}.txt", name, std::process::id()))
+    }
+
+    #[test]
+    fn inserts_highest_first_and_ties_below_older_scores() {
+        let mut table = HighScoreTable::default();
+        assert_eq!(table.insert("a".to_string(), 50), Some(0));
+        assert_eq!(table.insert("b".to_string(), 80), Some(0));
+        assert_eq!(table.insert("c".to_string(), 20), Some(2));
+        assert_eq!(table.insert("d".to_string(), 50), Some(2));
+        assert_eq!(scores(&table), [80, 50, 50, 20]);
+        assert_eq!(table.entries[1].name, "a");
+        assert_eq!(table.entries[2].name, "d");
+        assert_eq!(table.best(), 80);
+    }
+
+    #[test]
+    fn a_full_table_drops_its_lowest_score() {
+        let mut table = HighScoreTable::default();
+        for score in 1..=MAX_ENTRIES as u32 {
+            table.insert(score.to_string(), score * 10);
+        }
+        assert_eq!(table.insert("low".to_string(), 5), None);
+        assert_eq!(table.insert("high".to_string(), 55), Some(5));
+        assert_eq!(table.entries.len(), MAX_ENTRIES);
+        assert_eq!(table.entries.last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn a_tie_with_the_lowest_score_of_a_full_table_doesnt_qualify() {
+        let mut table = HighScoreTable::default();
+        assert!(!table.qualifies(0));
+        assert!(table.qualifies(1));
+        for score in 1..=MAX_ENTRIES as u32 {
+            table.insert(score.to_string(), score * 10);
+        }
+        assert!(!table.qualifies(10));
+        assert!(table.qualifies(11));
+        // it doesn't make it in either
+        assert_eq!(table.insert("tie".to_string(), 10), None);
+    }
+
+    #[test]
+    fn a_missing_file_is_an_empty_table() {
+        let table = HighScoreTable::load(&temp_path("missing")).unwrap();
+        assert!(table.entries.is_empty());
+    }
+
+    #[test]
+    fn loading_skips_broken_lines_and_sorts() {
+        let path = temp_path("broken");
+        fs::write(&path, "30\tb\nnot a score\n\nx\ty\n-5\tc\n70\ta\n").unwrap();
+        let table = HighScoreTable::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(scores(&table), [70, 30]);
+        assert_eq!(table.entries[0].name, "a");
+    }
+
+    #[test]
+    fn a_file_that_isnt_text_is_an_error() {
+        let path = temp_path("binary");
+        fs::write(&path, [0xff, 0xfe, 0x00, 0x80]).unwrap();
+        let error = HighScoreTable::load(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn saved_tables_load_the_same() {
+        let path = temp_path("saved");
+        let mut table = HighScoreTable::default();
+        table.insert("first".to_string(), 300);
+        table.insert("second one".to_string(), 100);
+        table.save(&path).unwrap();
+        let loaded = HighScoreTable::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entries, table.entries);
+    }
+}
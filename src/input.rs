@@ -0,0 +1,18 @@
+use macroquad::math::Vec2;
+
+/// Everything the simulation needs to know about the player's input for one step.
+/// Filled from the keyboard by the game loop, or by hand in tools and tests.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Input {
+    pub grab_pressed: bool,
+    pub grab_released: bool,
+    pub grab_down: bool,
+    pub boost_down: bool,
+    /// Shorten or lengthen the rope to the planet the player holds on to.
+    pub reel_in: bool,
+    pub reel_out: bool,
+    pub shoot_down: bool,
+    /// World position the player is aiming at, if they are aiming at all. Without it, shots
+    /// (and aimed grabs) go in the direction the player is flying.
+    pub aim: Option<Vec2>,
+}
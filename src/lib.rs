@@ -0,0 +1,30 @@
+#![allow(clippy::needless_arbitrary_self_type)]
+
+pub mod assets;
+pub mod camera;
+pub mod collision;
+pub mod config;
+pub mod config_watcher;
+pub mod controls;
+pub mod danger_zone;
+pub mod difficulty;
+pub mod enemy;
+pub mod fixed_timestep;
+pub mod game;
+pub mod gravity;
+pub mod high_scores;
+pub mod input;
+pub mod particle;
+pub mod particle_controller;
+pub mod particle_effect;
+pub mod particle_pool;
+pub mod planet;
+pub mod planet_field;
+pub mod player;
+pub mod projectile;
+pub mod random_generator;
+pub mod render;
+pub mod replay;
+pub mod score;
+pub mod spatial_hash;
+pub mod world;
@@ -1,175 +1,146 @@
-use macroquad::camera::Camera2D;
-use macroquad::color::BLACK;
-use macroquad::input::{is_key_pressed, is_key_released, KeyCode};
-use macroquad::math::Vec2;
-use macroquad::rand::RandGenerator;
-use macroquad::text::draw_text;
-use macroquad::time::get_frame_time;
-use macroquad::window::{
-    clear_background, next_frame, screen_height, screen_width, set_fullscreen,
-};
-
-use danger_zone::DangerZone;
-use planet::Planet;
-use player::Player;
-use random_generator::get_rand_generator;
-
-mod danger_zone;
-mod particle;
-mod particle_controller;
-mod planet;
-mod player;
-mod random_generator;
-
-#[macroquad::main("MyGame")]
-async fn main() {
-    // request_new_screen_size(1000f32, 800f32);
-    set_fullscreen(true);
-    let rand_num_generator: RandGenerator = get_rand_generator();
-
-    let mut player: Player = Player::new(50f32);
-    let mut camera: Camera2D = Camera2D::default();
-    let mut planets: Vec<Planet> = Vec::new();
-    let mut bottom_danger_zone: DangerZone = DangerZone::new(
-        Vec2 {
-            x: 0f32,
-            y: 1000f32,
-        },
-        Vec2 {
-            x: 3000f32,
-            y: 1200f32,
-        },
-        Vec2 { x: 0f32, y: -20f32 },
-        0.003,
-    );
-    let mut left_danger_zone: DangerZone = DangerZone::new(
-        Vec2 {
-            x: -1600f32,
-            y: 0f32,
-        },
-        Vec2 {
-            x: 1200f32,
-            y: 2000f32,
-        },
-        Vec2 { x: 0f32, y: 0f32 },
-        0.01,
-    );
-    let mut right_danger_zone: DangerZone = DangerZone::new(
-        Vec2 {
-            x: 1600f32,
-            y: 0f32,
-        },
-        Vec2 {
-            x: 1200f32,
-            y: 2000f32,
-        },
-        Vec2 { x: 0f32, y: 0f32 },
-        0.01,
-    );
-
-    for i in 0..10 {
-        planets.push(Planet::new(
-            Vec2 {
-                x: rand_num_generator.gen_range(0f32, screen_width()),
-                y: (i as f32) * -rand_num_generator.gen_range(10f32, 1000f32),
-            },
-            Vec2 {
-                x: rand_num_generator.gen_range(-30f32, 30f32), // not uniform!
-                y: rand_num_generator.gen_range(-30f32, 30f32),
-            },
-            rand_num_generator.gen_range(10f32, 100f32),
-        ));
-    }
+use std::path::{Path, PathBuf};
+
+use macroquad::input::{is_quit_requested, prevent_quit};
+use macroquad::window::{next_frame, set_fullscreen};
+
+use david_game::assets::Assets;
+use david_game::config::Config;
+use david_game::game::Game;
+use david_game::high_scores::HighScoreTable;
+use david_game::replay::Replay;
+
+const USAGE: &str = "usage: david-game [--seed <number>] [--gravity] [--config <file>] \
+                     [--record <file> | --replay <file>]\n\
+                     --record saves the last run when the game is closed, restarting replaces \
+                     the run recorded so far";
+
+struct Arguments {
+    seed: Option<u64>,
+    gravity_enabled: bool,
+    config_path: Option<PathBuf>,
+    record_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+}
 
-    camera.offset = Vec2 {
-        x: screen_width() * 0.5f32,
-        y: screen_height() * 0.5f32,
+// a config given with `--config` has to be there, the default one is optional
+fn load_config(path: &Path, is_required: bool) -> Config {
+    let result = if is_required && !path.exists() {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ))
+    } else {
+        Config::load(path)
     };
+    result.unwrap_or_else(|error| {
+        eprintln!("couldn't load config {}:\n{}", path.display(), error);
+        std::process::exit(1);
+    })
+}
 
-    loop {
-        let delta_time: f32 = get_frame_time();
+// tells what's wrong with the command line, how to use it, and quits
+fn exit_with_usage(problem: &str) -> ! {
+    eprintln!("{}\n{}", problem, USAGE);
+    std::process::exit(1);
+}
 
-        if !player.is_dead && is_key_pressed(KeyCode::Space) {
-            let mut nearest: (f32, Option<usize>) = (f32::INFINITY, None);
-            for (index, planet) in planets.iter().enumerate() {
-                let dist = (planet.position.x - player.position.x)
-                    * (planet.position.x - player.position.x)
-                    + (planet.position.y - player.position.y)
-                        * (planet.position.y - player.position.y);
+fn next_value(args: &mut impl Iterator<Item = String>, name: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| exit_with_usage(&format!("{} needs a value", name)))
+}
 
-                if dist < nearest.0 {
-                    nearest.0 = dist;
-                    nearest.1 = Some(index);
-                }
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments {
+        seed: None,
+        gravity_enabled: false,
+        config_path: None,
+        record_path: None,
+        replay_path: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let seed = next_value(&mut args, "--seed");
+                arguments.seed = Some(seed.parse().unwrap_or_else(|_| {
+                    exit_with_usage(&format!("--seed needs a number, not `{}`", seed))
+                }));
             }
-            match nearest.1 {
-                Some(index) => player.linked_planet_index = Some(index),
-                None => player.linked_planet_index = None,
+            "--gravity" => arguments.gravity_enabled = true,
+            "--config" => {
+                arguments.config_path = Some(PathBuf::from(next_value(&mut args, "--config")))
             }
-        }
-
-        if player.is_dead || is_key_released(KeyCode::Space) {
-            player.let_go_of_planet(&planets);
-        }
-
-        player.update_camera(&mut camera);
-        player.update(&planets, delta_time);
-
-        bottom_danger_zone.update_as_bottom_zone(delta_time, player.position.x);
-        bottom_danger_zone.check_and_handle_player_collision(&mut player);
-        if !player.is_dead {
-            left_danger_zone.update_as_side_zone(delta_time, player.position.y);
-            right_danger_zone.update_as_side_zone(delta_time, player.position.y);
-            left_danger_zone.check_and_handle_player_collision(&mut player);
-            right_danger_zone.check_and_handle_player_collision(&mut player);
-            if player.is_dead {
-                bottom_danger_zone.position.y = player.position.y + 600f32;
+            "--record" => {
+                arguments.record_path = Some(PathBuf::from(next_value(&mut args, "--record")))
+            }
+            "--replay" => {
+                arguments.replay_path = Some(PathBuf::from(next_value(&mut args, "--replay")))
             }
+            _ => exit_with_usage(&format!("unknown argument `{}`", arg)),
         }
+    }
+    if arguments.record_path.is_some() && arguments.replay_path.is_some() {
+        exit_with_usage("--record and --replay can't be used together");
+    }
+    arguments
+}
 
-        for planet in &mut planets {
-            planet.update(delta_time);
-        }
+#[macroquad::main("MyGame")]
+async fn main() {
+    let arguments = parse_arguments();
+    let config_path = arguments
+        .config_path
+        .clone()
+        .unwrap_or_else(Config::default_path);
+    let config = load_config(&config_path, arguments.config_path.is_some());
 
-        for planet in &mut planets {
-            if (player.position - planet.position).length() < player.radius + planet.radius {
-                player.handle_collistion(planet);
-            }
+    // request_new_screen_size(1000f32, 800f32);
+    set_fullscreen(true);
+    prevent_quit();
+
+    let game: Game = match &arguments.replay_path {
+        Some(path) => {
+            let replay = Replay::load(path)
+                .and_then(|replay| replay.check_config(&config).map(|_| replay))
+                .unwrap_or_else(|error| {
+                    eprintln!("couldn't load replay {}:\n{}", path.display(), error);
+                    std::process::exit(1);
+                });
+            Game::from_replay(replay)
         }
-
-        for i in 0..(planets.len() - 1) {
-            let (left, right) = planets.split_at_mut(i + 1);
-            for j in 0..right.len() {
-                if (left[i].position - right[j].position).length()
-                    < left[i].radius + right[j].radius
-                {
-                    left[i].handle_collistion(&mut right[j]);
-                }
+        None => {
+            let game = Game::new(arguments.seed, arguments.gravity_enabled);
+            match HighScoreTable::default_path() {
+                Some(path) => game.with_high_scores(path),
+                None => game,
             }
         }
+    };
+    let game = game
+        .with_config(config)
+        .with_assets(&Assets::default_path());
+    // a replay only plays back the same with the config it was recorded with, so that one stays
+    let mut game = if arguments.replay_path.is_some() {
+        game
+    } else {
+        game.with_config_watcher(config_path)
+    };
 
-        clear_background(BLACK);
-        
-        player.draw(&planets, &camera);
-        bottom_danger_zone.draw(&camera);
-        if !player.is_dead {
-            left_danger_zone.draw(&camera);
-            right_danger_zone.draw(&camera);
-        }
-        for planet in &planets {
-            planet.draw(&camera);
+    loop {
+        if is_quit_requested() {
+            break;
         }
 
-        if player.is_dead {
-            draw_text(
-                "YOU DIED LOSER",
-                screen_width() / 2f32 - 306.25 / 2f32,
-                screen_height() / 2f32 - 25f32 / 2f32,
-                50f32,
-                BLACK,
-            );
-        }
+        game.update();
+        game.draw();
 
         next_frame().await
     }
+
+    if let Some(path) = &arguments.record_path {
+        if let Err(error) = game.replay.save(path) {
+            eprintln!("couldn't save replay {}:\n{}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
 }
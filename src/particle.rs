@@ -1,38 +1,179 @@
-use macroquad::{camera::Camera2D, color::Color, math::Vec2, shapes::draw_circle};
+use macroquad::{color::Color, math::Vec2};
+use serde::Deserialize;
 
+/// `Particle::anchor` of particles that don't move along with anything.
+pub const NO_ANCHOR: u32 = u32::MAX;
+
+/// What emitted a particle, so whole groups can be hidden when drawing (e.g. when a sprite is
+/// drawn instead).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticleGroup {
+    Player,
+    PlayerTrail,
+    Planet,
+    Enemy,
+    Projectile,
+    BottomZone,
+    SideZone,
+}
+
+/// How a particle gets from its start to its end color and radius over its life.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// At the same pace all the way.
+    #[default]
+    Linear,
+    /// Slowly at first, then faster.
+    EaseIn,
+    /// Quickly at first, then slower.
+    EaseOut,
+    /// Slowly at the start and the end.
+    EaseInOut,
+}
+
+impl Easing {
+    /// How far (0 to 1) along to the end value a particle is at `age` (0 to 1).
+    pub fn apply(self: &Self, age: f32) -> f32 {
+        let t = age.clamp(0f32, 1f32);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1f32 - (1f32 - t) * (1f32 - t),
+            Easing::EaseInOut => t * t * (3f32 - 2f32 * t),
+        }
+    }
+}
+
+// every channel stays between 0 and 1, whatever the colors
+fn mix_colors(from: Color, to: Color, t: f32) -> Color {
+    let mix = |from: f32, to: f32| (from + (to - from) * t).clamp(0f32, 1f32);
+    Color::new(
+        mix(from.r, to.r),
+        mix(from.g, to.g),
+        mix(from.b, to.b),
+        mix(from.a, to.a),
+    )
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct Particle {
     pub position: Vec2,
     pub velocity: Vec2,
+    /// Radius and color right now, somewhere between the start and end ones depending on age.
     pub radius: f32,
     pub color: Color,
+    pub start_radius: f32,
+    pub end_radius: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub easing: Easing,
+    /// Added to `velocity` every second, like gravity.
+    pub acceleration: Vec2,
+    /// Fraction of its speed lost every second.
+    pub drag: f32,
+    /// Seconds the particle lives in total.
+    pub lifespan: f32,
     pub time_left: f32,
+    /// Index of the anchor in the pool the particle moves along with, or `NO_ANCHOR`.
+    pub anchor: u32,
+    pub group: ParticleGroup,
 }
 
 impl Particle {
-    pub fn new(position: Vec2, velocity: Vec2, radius: f32, color: Color, time_left: f32) -> Self {
+    /// A particle that keeps `radius` and `color` all its life, until the end fields are set.
+    pub fn new(
+        position: Vec2,
+        velocity: Vec2,
+        radius: f32,
+        color: Color,
+        lifespan: f32,
+        group: ParticleGroup,
+    ) -> Self {
         Self {
-            position: position,
-            velocity: velocity,
-            radius: radius,
-            color: color,
-            time_left: time_left,
+            position,
+            velocity,
+            radius,
+            color,
+            start_radius: radius,
+            end_radius: radius,
+            start_color: color,
+            end_color: color,
+            easing: Easing::Linear,
+            acceleration: Vec2::ZERO,
+            drag: 0f32,
+            lifespan,
+            time_left: lifespan,
+            anchor: NO_ANCHOR,
+            group,
         }
     }
-    pub fn draw(self: &Self, camera: &Camera2D) {
-        draw_circle(
-            self.position.x - camera.target.x + camera.offset.x,
-            self.position.y - camera.target.y + camera.offset.y,
-            self.radius,
-            self.color,
-        );
+
+    /// How much of its life (0 to 1) the particle has behind it.
+    pub fn age(self: &Self) -> f32 {
+        if self.lifespan <= 0f32 {
+            return 1f32;
+        }
+        (1f32 - self.time_left / self.lifespan).clamp(0f32, 1f32)
     }
 
     pub fn update(self: &mut Self, delta_time: f32) {
         self.time_left -= delta_time;
+        self.velocity += self.acceleration * delta_time;
+        self.velocity *= (1f32 - self.drag * delta_time).max(0f32);
         self.position.x += self.velocity.x * delta_time;
         self.position.y += self.velocity.y * delta_time;
-        if self.time_left < self.color.a {
-            self.color.a = self.time_left;
+
+        let t = self.easing.apply(self.age());
+        self.radius = (self.start_radius + (self.end_radius - self.start_radius) * t).max(0f32);
+        self.color = mix_colors(self.start_color, self.end_color, t);
+        // fade out over the last moments however opaque the end color is, so nothing pops away
+        self.color.a = self.color.a.min(self.time_left.max(0f32));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ];
+
+    #[test]
+    fn easings_go_from_0_to_1() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(0f32), 0f32, "{:?}", easing);
+            assert_eq!(easing.apply(1f32), 1f32, "{:?}", easing);
+            // ages past the ends count as the ends
+            assert_eq!(easing.apply(-0.5), 0f32, "{:?}", easing);
+            assert_eq!(easing.apply(1.5), 1f32, "{:?}", easing);
         }
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn mixed_colors_start_and_end_at_the_colors_and_stay_in_range() {
+        let from = Color::new(0f32, 0.5, 1f32, 1f32);
+        let to = Color::new(1f32, 0.5, 0f32, 0f32);
+        assert_eq!(mix_colors(from, to, 0f32), from);
+        assert_eq!(mix_colors(from, to, 1f32), to);
+        assert_eq!(mix_colors(from, to, 0.5), Color::new(0.5, 0.5, 0.5, 0.5));
+        // out of range colors or mixing past the end still give valid channels
+        let bright = Color::new(2f32, -1f32, 0.5, 1f32);
+        assert_eq!(
+            mix_colors(bright, to, 0f32),
+            Color::new(1f32, 0f32, 0.5, 1f32)
+        );
+        assert_eq!(
+            mix_colors(from, to, 2f32),
+            Color::new(1f32, 0.5, 0f32, 0f32)
+        );
     }
 }
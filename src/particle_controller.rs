@@ -1,121 +1,107 @@
-use std::f32::consts::PI;
-
-use crate::particle::Particle;
-use crate::random_generator::get_rand_generator;
-use macroquad::{camera::Camera2D, color::Color, math::Vec2, rand::RandGenerator};
+use crate::particle::{Particle, ParticleGroup};
+use crate::particle_effect::{ParticleEffect, SpawnShape};
+use crate::particle_pool::{AnchorHandle, ParticlePool};
+use macroquad::math::Vec2;
 
+/// Emits particles into the world's `ParticlePool` the way its effect says. Only knows how and
+/// how often to spawn, the particles themselves live in the pool.
 pub struct ParticleController {
-    pub particles: Vec<Particle>,
+    pub group: ParticleGroup,
+    pub effect: ParticleEffect,
+    /// What the effect's sizes are relative to.
+    pub size: f32,
+    /// Width and height around the emitter's position that `SpawnShape::Box` spawns in.
+    pub area: Vec2,
     pub spawn_timer: f32,
-    pub time_per_particle: f32,
-    pub initial_velocity: f32,
-    pub initial_radius: f32,
-    pub initial_color: Color,
-    pub lifespan: f32,
-    pub random_generator: RandGenerator,
+    // particles move along with the owner through this, once it moves
+    anchor: Option<AnchorHandle>,
 }
 
 impl ParticleController {
-    pub fn new(
-        time_per_particle: f32,
-        initial_velocity: f32,
-        initial_radius: f32,
-        initial_color: Color,
-        lifespan: f32,
-    ) -> Self {
+    pub fn new(group: ParticleGroup, effect: ParticleEffect, size: f32) -> Self {
         Self {
-            particles: Vec::new(),
-            spawn_timer: time_per_particle,
-            time_per_particle: time_per_particle,
-            initial_velocity: initial_velocity,
-            initial_radius: initial_radius,
-            initial_color: initial_color,
-            lifespan: lifespan,
-            random_generator: get_rand_generator(),
-        }
-    }
-    pub fn draw(self: &Self, camera: &Camera2D) {
-        for i in 0..self.particles.len() {
-            self.particles[i].draw(camera);
+            group,
+            effect,
+            size,
+            area: Vec2::splat(size * 2f32),
+            spawn_timer: effect.time_per_particle,
+            anchor: None,
         }
     }
-    pub fn inherit_movement(self: &mut Self, change: Vec2) {
-        for i in 0..self.particles.len() {
-            self.particles[i].position.x += change.x;
-            self.particles[i].position.y += change.y;
-        }
-    }
-    pub fn shift_color(self: &mut Self, r: f32, g: f32, b: f32, a: f32) {
-        for i in 0..self.particles.len() {
-            self.particles[i].color.r += r;
-            self.particles[i].color.g += g;
-            self.particles[i].color.b += b;
-            self.particles[i].color.a += a;
-        }
+    /// Moves the particles spawned so far along with the owner. Has to be called every step
+    /// for particles to keep following it.
+    pub fn inherit_movement(self: &mut Self, pool: &mut ParticlePool, change: Vec2) {
+        pool.move_anchor(&mut self.anchor, change);
     }
-    pub fn update(self: &mut Self, delta_time: f32, position: Vec2) {
-        for i in 0..self.particles.len() {
-            self.particles[i].update(delta_time);
-        }
-        self.particles.retain(|particle| particle.time_left > 0.0);
-        self.spawn_timer -= delta_time;
+    pub fn update(self: &mut Self, pool: &mut ParticlePool, delta_time: f32, position: Vec2) {
+        let effect = &self.effect;
+        let radius = self.size * effect.radius_per_size.max(effect.end_radius_per_size());
+        // the box particle centers go into, a point for the other shapes. Off screen, there are
+        // fewer particles the further away, and only the visible part of a box gets any
+        let (from_position, to_position, rate) = match effect.shape {
+            SpawnShape::Box => {
+                let half_size = (self.area * 0.5 - Vec2::splat(radius)).max(Vec2::ZERO);
+                match pool.visible_range(position - half_size, position + half_size) {
+                    Some(range) => range,
+                    None => return,
+                }
+            }
+            SpawnShape::Point | SpawnShape::Ring { .. } => {
+                let ring_radius = match effect.shape {
+                    SpawnShape::Ring { radius } => radius,
+                    _ => 0f32,
+                };
+                let travel =
+                    effect.velocity_per_size * (1f32 + effect.speed_spread) * effect.lifespan;
+                let extent = self.size * (ring_radius + travel) + radius;
+                (position, position, pool.spawn_rate(position, extent))
+            }
+        };
+        self.spawn_timer -= delta_time * rate;
 
         while self.spawn_timer <= 0.0 {
-            self.spawn_timer += self.time_per_particle;
-            self.spawn(position);
+            self.spawn_timer += self.effect.time_per_particle;
+            // the pool is full, the particles owed so far are dropped along with this one
+            if !self.spawn(pool, from_position, to_position) {
+                self.spawn_timer = self.effect.time_per_particle;
+                break;
+            }
         }
     }
-    pub fn update_with_range(
-        self: &mut Self,
-        delta_time: f32,
-        from_position: Vec2,
-        to_position: Vec2,
-    ) {
-        for i in 0..self.particles.len() {
-            self.particles[i].update(delta_time);
+    // somewhere between `from_position` and `to_position`, shapes other than boxes pass the
+    // same position twice. `false` once the pool is full
+    fn spawn(self: &Self, pool: &mut ParticlePool, from_position: Vec2, to_position: Vec2) -> bool {
+        let effect = &self.effect;
+        let rng = &pool.random_generator;
+        let mut position = Vec2 {
+            x: rng.gen_range(from_position.x, to_position.x),
+            y: rng.gen_range(from_position.y, to_position.y),
+        };
+        if let SpawnShape::Ring { radius } = effect.shape {
+            position +=
+                Vec2::from_angle(rng.gen_range(0f32, std::f32::consts::TAU)) * radius * self.size;
         }
-        self.particles.retain(|particle| particle.time_left > 0.0);
-        self.spawn_timer -= delta_time;
+        let angle = effect.direction + rng.gen_range(-0.5, 0.5) * effect.spread;
+        let speed = self.size
+            * effect.velocity_per_size
+            * (1f32 + rng.gen_range(-1f32, 1f32) * effect.speed_spread);
 
-        while self.spawn_timer <= 0.0 {
-            self.spawn_timer += self.time_per_particle;
-            self.spawn_in_range(from_position, to_position);
-        }
-    }
-    pub fn spawn(self: &mut Self, position: Vec2) {
-        // TODO: skip if not on screen
-        let rand_angle = self.random_generator.gen_range(0.0, 2.0 * PI);
-        self.particles.push(Particle::new(
+        let mut particle = Particle::new(
             position,
-            Vec2 {
-                x: rand_angle.cos() * self.initial_velocity,
-                y: rand_angle.sin() * self.initial_velocity,
-            },
-            self.initial_radius,
-            self.initial_color,
-            self.lifespan,
-        ));
-    }
-    pub fn spawn_in_range(self: &mut Self, from_position: Vec2, to_position: Vec2) {
-        let rand_angle = self.random_generator.gen_range(0.0, 2.0 * PI);
-        self.particles.push(Particle::new(
-            Vec2 {
-                x: self.random_generator.gen_range(
-                    from_position.x + self.initial_radius,
-                    to_position.x - self.initial_radius,
-                ),
-                y: self.random_generator.gen_range(
-                    from_position.y + self.initial_radius,
-                    to_position.y - self.initial_radius,
-                ),
-            },
-            Vec2 {
-                x: rand_angle.cos() * self.initial_velocity,
-                y: rand_angle.sin() * self.initial_velocity,
-            },
-            self.initial_radius,
-            self.initial_color,
-            self.lifespan,
-        ));
+            Vec2::from_angle(angle) * speed,
+            self.size * effect.radius_per_size,
+            effect.color(),
+            effect.lifespan,
+            self.group,
+        );
+        particle.end_radius = self.size * effect.end_radius_per_size();
+        particle.end_color = effect.end_color();
+        particle.easing = effect.easing;
+        particle.acceleration = effect.gravity.into();
+        particle.drag = effect.drag;
+        if let Some(anchor) = pool.anchor_index(self.anchor) {
+            particle.anchor = anchor;
+        }
+        pool.spawn(particle)
     }
 }
@@ -0,0 +1,105 @@
+use std::f32::consts::TAU;
+
+use macroquad::color::Color;
+use serde::Deserialize;
+
+use crate::particle::{Easing, ParticleGroup};
+use crate::particle_controller::ParticleController;
+
+/// `ParticleEffect::time_per_particle` can't be any shorter than this.
+pub const MIN_TIME_PER_PARTICLE: f32 = 1e-4;
+
+/// Where particles start, relative to whatever emits them.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SpawnShape {
+    /// Right where the emitter is.
+    Point,
+    /// Anywhere in the emitter's area, like all over a danger zone.
+    Box,
+    /// On a circle around the emitter, `radius` is relative to its size.
+    Ring { radius: f32 },
+}
+
+/// What an emitter's particles look like and how they move, as data so it can be tuned in the
+/// config. Speeds, radii and distances are relative to the size of whatever emits them (its
+/// radius, or a zone's longest side), so one effect fits planets of every size.
+///
+/// Colors and radii go from their start to their end value over a particle's life, as fast as
+/// `easing` says, or stay the same without one. Colors are red, green, blue and alpha (0 to 1),
+/// and particles fade out at the very end of their life no matter what.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParticleEffect {
+    /// Seconds between two particles.
+    pub time_per_particle: f32,
+    /// Seconds a particle lives.
+    pub lifespan: f32,
+    pub shape: SpawnShape,
+    pub velocity_per_size: f32,
+    /// Where particles fly off to in radians, 0 is to the right and a quarter turn down.
+    pub direction: f32,
+    /// Angle in radians around `direction` that particles spread over, a full turn is every
+    /// way at once.
+    pub spread: f32,
+    /// Speeds are up to this much (as a fraction) faster or slower.
+    pub speed_spread: f32,
+    pub radius_per_size: f32,
+    pub end_radius_per_size: Option<f32>,
+    pub color: [f32; 4],
+    pub end_color: Option<[f32; 4]>,
+    pub easing: Easing,
+    /// Acceleration in units per second squared, down is positive y.
+    pub gravity: [f32; 2],
+    /// Fraction of their speed particles lose every second.
+    pub drag: f32,
+}
+
+impl ParticleEffect {
+    pub fn color(self: &Self) -> Color {
+        let [r, g, b, a] = self.color;
+        Color::new(r, g, b, a)
+    }
+
+    pub fn end_color(self: &Self) -> Color {
+        let [r, g, b, a] = self.end_color.unwrap_or(self.color);
+        Color::new(r, g, b, a)
+    }
+
+    pub fn end_radius_per_size(self: &Self) -> f32 {
+        self.end_radius_per_size.unwrap_or(self.radius_per_size)
+    }
+
+    pub fn controller(self: &Self, size: f32, group: ParticleGroup) -> ParticleController {
+        ParticleController::new(group, *self, size)
+    }
+
+    /// Makes `controller` spawn particles like this from now on, the ones that are already
+    /// flying around stay as they are.
+    pub fn apply_to(self: &Self, controller: &mut ParticleController, size: f32) {
+        controller.spawn_timer = controller.spawn_timer.min(self.time_per_particle);
+        controller.effect = *self;
+        controller.size = size;
+    }
+}
+
+impl Default for ParticleEffect {
+    fn default() -> Self {
+        Self {
+            time_per_particle: 0.01,
+            lifespan: 0.5,
+            shape: SpawnShape::Point,
+            velocity_per_size: 1.2,
+            direction: 0f32,
+            spread: TAU,
+            speed_spread: 0f32,
+            radius_per_size: 0.4,
+            end_radius_per_size: None,
+            color: [1f32, 1f32, 1f32, 0.2],
+            end_color: None,
+            easing: Easing::Linear,
+            gravity: [0f32, 0f32],
+            drag: 0f32,
+        }
+    }
+}
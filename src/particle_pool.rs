@@ -0,0 +1,276 @@
+use macroquad::math::{Rect, Vec2};
+use macroquad::rand::RandGenerator;
+
+use crate::particle::{Particle, NO_ANCHOR};
+use crate::random_generator::get_rand_generator;
+
+/// Particles the world's pool has room for. More than the game ever has alive at once (the
+/// dying bottom zone is the most, with a few thousand), spawns are dropped beyond it.
+pub const MAX_PARTICLES: usize = 32768;
+
+/// Emitters this close outside the view still spawn as if they were on it, so there's a cloud
+/// around things by the time the camera gets to them.
+pub const VIEW_MARGIN: f32 = 300f32;
+/// Further out than `VIEW_MARGIN`, spawning slows down until it stops this much further away.
+pub const LOD_DISTANCE: f32 = 1500f32;
+
+/// Identifies an anchor as long as it stays in use, see `ParticlePool::hold_anchor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnchorHandle {
+    index: u32,
+    generation: u32,
+}
+
+// Something particles move along with, like a planet carrying its particle cloud.
+struct Anchor {
+    movement: Vec2,
+    last_movement: Vec2,
+    particle_count: u32,
+    generation: u32,
+    is_held: bool,
+    is_free: bool,
+}
+
+/// Every particle in the world, in one list with a fixed capacity that is allocated up front.
+/// Emitters (`ParticleController`s) write into it, and dead particles are swapped out with the
+/// last one, so keeping particles alive never allocates and never moves more than one of them.
+///
+/// Particles can't point at whatever emitted them, so the ones that should move along with it
+/// point at an anchor instead. The emitter moves the anchor, and the pool moves its particles in
+/// the same pass that updates them. Anchors nobody holds on to anymore are reused once their
+/// last particle is gone.
+pub struct ParticlePool {
+    particles: Vec<Particle>,
+    capacity: usize,
+    anchors: Vec<Anchor>,
+    free_anchors: Vec<u32>,
+    /// Shared by all emitters, so they don't each need their own.
+    pub random_generator: RandGenerator,
+    /// The part of the world that's on screen, set by whoever draws. Emitters away from it
+    /// spawn fewer particles or none, `None` counts everything as on screen.
+    pub view: Option<Rect>,
+}
+
+impl ParticlePool {
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+            anchors: Vec::new(),
+            free_anchors: Vec::new(),
+            random_generator: get_rand_generator(seed),
+            view: None,
+        }
+    }
+
+    pub fn particles(self: &Self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Adds `particle` unless the pool is full, then it's dropped and `false` returned.
+    pub fn spawn(self: &mut Self, particle: Particle) -> bool {
+        if self.particles.len() >= self.capacity {
+            return false;
+        }
+        if particle.anchor != NO_ANCHOR {
+            self.anchors[particle.anchor as usize].particle_count += 1;
+        }
+        self.particles.push(particle);
+        true
+    }
+
+    /// How much of its usual rate (0 to 1) an emitter at `position` spawns, whose particles get
+    /// up to `extent` away from it: all of it near the view, less and less further away.
+    pub fn spawn_rate(self: &Self, position: Vec2, extent: f32) -> f32 {
+        let Some(view) = self.view else {
+            return 1f32;
+        };
+        let outside = Vec2::new(
+            (view.left() - position.x).max(position.x - view.right()),
+            (view.top() - position.y).max(position.y - view.bottom()),
+        )
+        .max(Vec2::ZERO);
+        let distance = outside.length() - extent - VIEW_MARGIN;
+        (1f32 - distance / LOD_DISTANCE).clamp(0f32, 1f32)
+    }
+
+    /// The part of the box from `from` to `to` that is near the view, and how much of the box
+    /// (0 to 1) that is. `None` when none of it is.
+    pub fn visible_range(self: &Self, from: Vec2, to: Vec2) -> Option<(Vec2, Vec2, f32)> {
+        let Some(view) = self.view else {
+            return Some((from, to, 1f32));
+        };
+        let area = (to - from).max(Vec2::ZERO);
+        if area.x * area.y <= 0f32 {
+            return Some((from, to, 1f32));
+        }
+        let visible_from = from.max(Vec2::new(view.left(), view.top()) - VIEW_MARGIN);
+        let visible_to = to.min(Vec2::new(view.right(), view.bottom()) + VIEW_MARGIN);
+        let visible_area = visible_to - visible_from;
+        if visible_area.x <= 0f32 || visible_area.y <= 0f32 {
+            return None;
+        }
+        let fraction = visible_area.x * visible_area.y / (area.x * area.y);
+        Some((visible_from, visible_to, fraction))
+    }
+
+    /// Index of the anchor `handle` stands for, unless it has been reused since.
+    pub fn anchor_index(self: &Self, handle: Option<AnchorHandle>) -> Option<u32> {
+        let handle = handle?;
+        let anchor = self.anchors.get(handle.index as usize)?;
+        (!anchor.is_free && anchor.generation == handle.generation).then_some(handle.index)
+    }
+
+    /// Keeps the anchor in `handle` from being reused for another step, or gets a new one if
+    /// there is none yet or it already was. Has to be called every step by whoever moves it.
+    pub fn hold_anchor(self: &mut Self, handle: &mut Option<AnchorHandle>) -> u32 {
+        let index = match self.anchor_index(*handle) {
+            Some(index) => index,
+            None => {
+                let index = match self.free_anchors.pop() {
+                    Some(index) => index,
+                    None => {
+                        self.anchors.push(Anchor {
+                            movement: Vec2::ZERO,
+                            last_movement: Vec2::ZERO,
+                            particle_count: 0,
+                            generation: 0,
+                            is_held: false,
+                            is_free: true,
+                        });
+                        self.anchors.len() as u32 - 1
+                    }
+                };
+                let anchor = &mut self.anchors[index as usize];
+                anchor.is_free = false;
+                anchor.movement = Vec2::ZERO;
+                anchor.last_movement = Vec2::ZERO;
+                *handle = Some(AnchorHandle {
+                    index,
+                    generation: anchor.generation,
+                });
+                index
+            }
+        };
+        self.anchors[index as usize].is_held = true;
+        index
+    }
+
+    /// Moves all particles of the anchor by `change` in the next `update`.
+    pub fn move_anchor(self: &mut Self, handle: &mut Option<AnchorHandle>, change: Vec2) {
+        let index = self.hold_anchor(handle);
+        self.anchors[index as usize].movement += change;
+    }
+
+    /// How far the anchor moved in the last step, for interpolating its particles.
+    pub fn anchor_movement(self: &Self, index: u32) -> Vec2 {
+        self.anchors[index as usize].last_movement
+    }
+
+    /// Moves and ages every particle, removes the dead ones and frees anchors that nobody
+    /// holds and no particle uses anymore. Call once per step, after the emitters.
+    pub fn update(self: &mut Self, delta_time: f32) {
+        for anchor in &mut self.anchors {
+            anchor.particle_count = 0;
+        }
+
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &mut self.particles[i];
+            if particle.anchor != NO_ANCHOR {
+                particle.position += self.anchors[particle.anchor as usize].movement;
+            }
+            particle.update(delta_time);
+            if particle.time_left <= 0f32 {
+                // the last particle takes its place and is looked at next
+                self.particles.swap_remove(i);
+                continue;
+            }
+            if particle.anchor != NO_ANCHOR {
+                self.anchors[particle.anchor as usize].particle_count += 1;
+            }
+            i += 1;
+        }
+
+        for (index, anchor) in self.anchors.iter_mut().enumerate() {
+            anchor.last_movement = anchor.movement;
+            anchor.movement = Vec2::ZERO;
+            if !anchor.is_free && !anchor.is_held && anchor.particle_count == 0 {
+                anchor.is_free = true;
+                anchor.generation = anchor.generation.wrapping_add(1);
+                self.free_anchors.push(index as u32);
+            }
+            anchor.is_held = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with_view() -> ParticlePool {
+        let mut pool = ParticlePool::new(16, 1);
+        pool.view = Some(Rect::new(0f32, 0f32, 1000f32, 800f32));
+        pool
+    }
+
+    #[test]
+    fn spawn_rate_falls_off_past_the_margin() {
+        let pool = pool_with_view();
+        let right_of_view = |distance: f32| Vec2::new(1000f32 + distance, 400f32);
+        assert_eq!(pool.spawn_rate(Vec2::new(500f32, 400f32), 0f32), 1f32);
+        assert_eq!(pool.spawn_rate(right_of_view(VIEW_MARGIN), 0f32), 1f32);
+        assert_eq!(
+            pool.spawn_rate(right_of_view(VIEW_MARGIN + LOD_DISTANCE * 0.5), 0f32),
+            0.5
+        );
+        assert_eq!(
+            pool.spawn_rate(right_of_view(VIEW_MARGIN + LOD_DISTANCE), 0f32),
+            0f32
+        );
+        assert_eq!(
+            pool.spawn_rate(right_of_view(VIEW_MARGIN + LOD_DISTANCE * 2f32), 0f32),
+            0f32
+        );
+        // particles that fly far count from where they get to
+        assert_eq!(
+            pool.spawn_rate(right_of_view(VIEW_MARGIN + 200f32), 200f32),
+            1f32
+        );
+        // diagonally off a corner counts the distance to the corner
+        let corner_distance = (VIEW_MARGIN + LOD_DISTANCE) / 2f32.sqrt();
+        let corner = Vec2::new(1000f32, 800f32) + Vec2::splat(corner_distance);
+        assert!(pool.spawn_rate(corner, 0f32) < 1e-3);
+    }
+
+    #[test]
+    fn spawn_rate_without_a_view_is_full() {
+        let pool = ParticlePool::new(16, 1);
+        assert_eq!(pool.spawn_rate(Vec2::splat(1e6), 0f32), 1f32);
+    }
+
+    #[test]
+    fn visible_range_cuts_boxes_to_the_view_and_its_margin() {
+        let pool = pool_with_view();
+        let inside = (Vec2::new(100f32, 100f32), Vec2::new(900f32, 700f32));
+        assert_eq!(
+            pool.visible_range(inside.0, inside.1),
+            Some((inside.0, inside.1, 1f32))
+        );
+        // a third of it sticks out further than the margin
+        let (from, to, fraction) = pool
+            .visible_range(Vec2::new(500f32, 100f32), Vec2::new(1700f32, 300f32))
+            .unwrap();
+        assert_eq!(from, Vec2::new(500f32, 100f32));
+        assert_eq!(to, Vec2::new(1000f32 + VIEW_MARGIN, 300f32));
+        assert!((fraction - 2f32 / 3f32).abs() < 1e-6);
+        let far_away = Vec2::new(1000f32 + VIEW_MARGIN + 1f32, 0f32);
+        assert_eq!(pool.visible_range(far_away, far_away + 100f32), None);
+        // a box without area is a point, it's left alone
+        assert_eq!(
+            pool.visible_range(far_away, far_away),
+            Some((far_away, far_away, 1f32))
+        );
+    }
+}
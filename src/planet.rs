@@ -1,101 +1,102 @@
+use crate::collision::Body;
+use crate::particle::ParticleGroup;
 use crate::particle_controller::ParticleController;
-use macroquad::{camera::Camera2D, color::Color, math::Vec2};
+use crate::particle_effect::ParticleEffect;
+use crate::particle_pool::ParticlePool;
+use crate::random_generator::get_rand_generator;
+use macroquad::math::Vec2;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlanetSettings {
+    /// Mass per area, planets are treated as flat discs.
+    pub density: f32,
+    /// Radians per second, only shows with a sprite.
+    pub max_spin: f32,
+    pub particles: ParticleEffect,
+}
 
-const PLANET_COLOR: Color = Color {
-    r: 0.2,
-    g: 0.4,
-    b: 0.2,
-    a: 0.2,
-};
+impl Default for PlanetSettings {
+    fn default() -> Self {
+        Self {
+            density: 1f32,
+            max_spin: 0.5,
+            particles: ParticleEffect {
+                color: [0.2, 0.4, 0.2, 0.2],
+                end_color: Some([0.2, 0.9, 1.0, 0.2]),
+                ..ParticleEffect::default()
+            },
+        }
+    }
+}
 
 pub struct Planet {
     pub position: Vec2,
+    pub previous_position: Vec2,
     pub velocity: Vec2,
     pub radius: f32,
+    pub mass: f32,
+    /// Angle of the sprite in radians.
+    pub rotation: f32,
+    pub spin: f32,
     pub particle_controller: ParticleController,
 }
 
 impl Planet {
-    pub fn new(position: Vec2, velocity: Vec2, radius: f32) -> Self {
+    pub fn new(
+        position: Vec2,
+        velocity: Vec2,
+        radius: f32,
+        settings: &PlanetSettings,
+        seed: u64,
+    ) -> Self {
+        let rand_num_generator = get_rand_generator(seed);
         Self {
             radius,
+            mass: settings.density * radius * radius,
             position,
+            previous_position: position,
             velocity,
-            particle_controller: ParticleController::new(
-                0.01,
-                radius * 1.2,
-                radius * 0.4,
-                PLANET_COLOR,
-                0.5,
-            ),
+            rotation: rand_num_generator.gen_range(0f32, std::f32::consts::TAU),
+            spin: rand_num_generator.gen_range(-settings.max_spin, settings.max_spin),
+            particle_controller: settings.particles.controller(radius, ParticleGroup::Planet),
         }
     }
 
-    pub fn update(self: &mut Self, delta_time: f32) {
-        self.particle_controller.update(delta_time, self.position);
+    /// Switches to new tuning in the middle of a run. `old_settings` are the ones the planet was
+    /// made with, to keep its spin the same fraction of the maximum.
+    pub fn apply_settings(
+        self: &mut Self,
+        old_settings: &PlanetSettings,
+        settings: &PlanetSettings,
+    ) {
+        self.mass = settings.density * self.radius * self.radius;
+        self.spin = if old_settings.max_spin > 0f32 {
+            self.spin / old_settings.max_spin * settings.max_spin
+        } else {
+            0f32
+        };
+        settings
+            .particles
+            .apply_to(&mut self.particle_controller, self.radius);
+    }
+
+    pub fn update(self: &mut Self, particles: &mut ParticlePool, delta_time: f32) {
         self.particle_controller
-            .shift_color(0.0, delta_time, 2.0 * delta_time, 0.0);
+            .update(particles, delta_time, self.position);
         let change = self.velocity * delta_time;
         self.position += change;
-        self.particle_controller.inherit_movement(change);
+        self.particle_controller.inherit_movement(particles, change);
+        self.rotation = (self.rotation + self.spin * delta_time) % std::f32::consts::TAU;
     }
 
-    pub fn draw(self: &Self, camera: &Camera2D) {
-        self.particle_controller.draw(camera);
-    }
-
-    pub fn handle_collistion(self: &mut Self, other: &mut Planet) {
-        // position
-        let angle: f32 =
-            -(self.position.y - other.position.y).atan2(self.position.x - other.position.x);
-        let sin_angle = angle.sin();
-        let cos_angle = angle.cos();
-
-        let mut old_pos_x = self.position.x;
-        self.position.x = old_pos_x * cos_angle - self.position.y * sin_angle;
-        self.position.y = old_pos_x * sin_angle + self.position.y * cos_angle;
-
-        old_pos_x = other.position.x;
-        other.position.x = old_pos_x * cos_angle - other.position.y * sin_angle;
-        other.position.y = old_pos_x * sin_angle + other.position.y * cos_angle;
-
-        let dist_per_mass: f32 =
-            (-(self.position.x - other.position.x).abs() + self.radius + other.radius) / 2f32; // / (mass + e.mass);
-        self.position.x += dist_per_mass; // * e.mass;
-        other.position.x -= dist_per_mass; // * mass;
-
-        old_pos_x = self.position.x;
-        self.position.x = old_pos_x * cos_angle + self.position.y * sin_angle;
-        self.position.y = old_pos_x * (-sin_angle) + self.position.y * cos_angle;
-
-        old_pos_x = other.position.x;
-        other.position.x = old_pos_x * cos_angle + other.position.y * sin_angle;
-        other.position.y = old_pos_x * (-sin_angle) + other.position.y * cos_angle;
-
-        // velocity
-        let mut old_vel_x = self.velocity.x;
-        self.velocity.x = old_vel_x * cos_angle - self.velocity.y * sin_angle;
-        self.velocity.y = old_vel_x * sin_angle + self.velocity.y * cos_angle;
-
-        old_vel_x = other.velocity.x;
-        other.velocity.x = old_vel_x * cos_angle - other.velocity.y * sin_angle;
-        other.velocity.y = old_vel_x * sin_angle + other.velocity.y * cos_angle;
-
-        // let totalMass: f32 = mass + e.mass;
-        // let factor: f32 = (mass - e.mass) / totalMass;
-        // let prevVel: f32 = self.velocity.x;
-        // vel.x = factor * vel.x + 2 * e.vel.x * e.mass / totalMass;
-        // e.vel.x = 2 * prevVel * mass / totalMass - factor * e.vel.x;
-        let prev_vel: f32 = self.velocity.x;
-        self.velocity.x = other.velocity.x;
-        other.velocity.x = prev_vel;
-
-        old_vel_x = self.velocity.x;
-        self.velocity.x = old_vel_x * cos_angle + self.velocity.y * sin_angle;
-        self.velocity.y = old_vel_x * (-sin_angle) + self.velocity.y * cos_angle;
-
-        old_vel_x = other.velocity.x;
-        other.velocity.x = old_vel_x * cos_angle + other.velocity.y * sin_angle;
-        other.velocity.y = old_vel_x * (-sin_angle) + other.velocity.y * cos_angle;
+    pub fn body(self: &mut Self) -> Body<'_> {
+        Body {
+            position: &mut self.position,
+            velocity: &mut self.velocity,
+            radius: self.radius,
+            mass: self.mass,
+        }
     }
 }
@@ -0,0 +1,211 @@
+use macroquad::math::Vec2;
+
+use crate::difficulty::{altitude, DifficultyProfile};
+use crate::enemy::{Enemy, EnemySettings};
+use crate::planet::{Planet, PlanetSettings};
+use crate::random_generator::{get_rand_generator, indexed_seed, next_seed};
+
+/// Height of one horizontal band of planets.
+pub const CHUNK_HEIGHT: f32 = 1000f32;
+/// Planets are spawned between -FIELD_HALF_WIDTH and FIELD_HALF_WIDTH, the gap between the side
+/// danger zones.
+pub const FIELD_HALF_WIDTH: f32 = 1000f32;
+/// How far above the player chunks are generated, so planets never pop in on screen.
+pub const GENERATE_AHEAD: f32 = 4000f32;
+/// Mines drift with up to this speed on each axis.
+pub const MAX_MINE_DRIFT: f32 = 60f32;
+/// How far below the lower edge of the bottom danger zone planets are kept around.
+pub const DESPAWN_MARGIN: f32 = 1000f32;
+
+/// Streams planets (and the enemies between them) in as the player climbs. The world is split
+/// into bands of `CHUNK_HEIGHT`, chunk `i` covering `i * CHUNK_HEIGHT` to
+/// `(i + 1) * CHUNK_HEIGHT` (up is negative y, so chunks higher up have lower indices). Each
+/// chunk's planets only depend on the world seed, the chunk index and the difficulty profile, so
+/// the same seed always gives the same layout no matter when chunks are generated.
+pub struct PlanetField {
+    pub seed: u64,
+    pub planet_settings: PlanetSettings,
+    pub enemy_settings: EnemySettings,
+    /// The next chunk that will be generated, everything below it already was.
+    pub next_chunk: i64,
+}
+
+impl PlanetField {
+    pub fn new(seed: u64, planet_settings: PlanetSettings, enemy_settings: EnemySettings) -> Self {
+        Self {
+            seed,
+            planet_settings,
+            enemy_settings,
+            // the first chunk is the band right below the player's start
+            next_chunk: 0,
+        }
+    }
+
+    /// Generates all chunks up to `GENERATE_AHEAD` above `height`.
+    pub fn generate_up_to(
+        self: &mut Self,
+        height: f32,
+        difficulty: &DifficultyProfile,
+        planets: &mut Vec<Planet>,
+        enemies: &mut Vec<Enemy>,
+    ) {
+        while (self.next_chunk as f32 + 1f32) * CHUNK_HEIGHT > height - GENERATE_AHEAD {
+            self.generate_chunk(self.next_chunk, difficulty, planets, enemies);
+            self.next_chunk -= 1;
+        }
+    }
+
+    pub fn generate_chunk(
+        self: &Self,
+        chunk: i64,
+        difficulty: &DifficultyProfile,
+        planets: &mut Vec<Planet>,
+        enemies: &mut Vec<Enemy>,
+    ) {
+        let rng = get_rand_generator(indexed_seed(self.seed, chunk));
+        let top = chunk as f32 * CHUNK_HEIGHT;
+        let chunk_altitude = altitude(top + CHUNK_HEIGHT * 0.5);
+
+        // e.g. 2.3 planets per chunk means 2 planets, and a 30% chance of a third one
+        let planets_per_chunk = difficulty.planets_per_chunk.value_at(chunk_altitude);
+        let count = (planets_per_chunk + rng.gen_range(0f32, 1f32)).floor() as u32;
+        let min_radius = difficulty.min_planet_radius.value_at(chunk_altitude);
+        let max_radius = difficulty.max_planet_radius.value_at(chunk_altitude);
+        let max_drift = difficulty.max_planet_drift.value_at(chunk_altitude);
+
+        for _ in 0..count {
+            planets.push(Planet::new(
+                Vec2 {
+                    x: rng.gen_range(-FIELD_HALF_WIDTH, FIELD_HALF_WIDTH),
+                    y: rng.gen_range(top, top + CHUNK_HEIGHT),
+                },
+                Vec2 {
+                    x: rng.gen_range(-max_drift, max_drift), // not uniform!
+                    y: rng.gen_range(-max_drift, max_drift),
+                },
+                rng.gen_range(min_radius, max_radius),
+                &self.planet_settings,
+                next_seed(&rng),
+            ));
+        }
+
+        // enemies come after the planets, so they don't change where the planets are
+        let enemies_per_chunk = difficulty.enemies_per_chunk.value_at(chunk_altitude);
+        let enemy_count = (enemies_per_chunk + rng.gen_range(0f32, 1f32)).floor() as u32;
+        for _ in 0..enemy_count {
+            let position = Vec2 {
+                x: rng.gen_range(-FIELD_HALF_WIDTH, FIELD_HALF_WIDTH),
+                y: rng.gen_range(top, top + CHUNK_HEIGHT),
+            };
+            // orbiters need one of this chunk's planets to circle
+            let enemy = match rng.gen_range(0, 3) {
+                0 if count > 0 => {
+                    let planet_index =
+                        planets.len() - count as usize + rng.gen_range(0, count) as usize;
+                    Enemy::new_orbiter(position, planet_index, &self.enemy_settings)
+                }
+                1 => Enemy::new_mine(
+                    position,
+                    Vec2 {
+                        x: rng.gen_range(-MAX_MINE_DRIFT, MAX_MINE_DRIFT),
+                        y: rng.gen_range(-MAX_MINE_DRIFT, MAX_MINE_DRIFT),
+                    },
+                    &self.enemy_settings,
+                ),
+                _ => Enemy::new_chaser(position, &self.enemy_settings),
+            };
+            enemies.push(enemy);
+        }
+    }
+
+    /// Whether a planet has fallen so far below the bottom danger zone that it can be removed.
+    pub fn is_out_of_range(self: &Self, planet: &Planet, bottom_zone_lower_edge: f32) -> bool {
+        planet.position.y - planet.radius > bottom_zone_lower_edge + DESPAWN_MARGIN
+    }
+
+    /// Same as `is_out_of_range`, for enemies.
+    pub fn is_enemy_out_of_range(self: &Self, enemy: &Enemy, bottom_zone_lower_edge: f32) -> bool {
+        enemy.position.y - enemy.radius > bottom_zone_lower_edge + DESPAWN_MARGIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Curve;
+    use crate::enemy::EnemyKind;
+
+    // everything about a chunk's planets that generating it decides
+    fn planet_bits(planets: &[Planet]) -> Vec<[u32; 6]> {
+        planets
+            .iter()
+            .map(|planet| {
+                [
+                    planet.position.x.to_bits(),
+                    planet.position.y.to_bits(),
+                    planet.velocity.x.to_bits(),
+                    planet.velocity.y.to_bits(),
+                    planet.radius.to_bits(),
+                    planet.spin.to_bits(),
+                ]
+            })
+            .collect()
+    }
+
+    fn generate(field: &PlanetField, chunk: i64, difficulty: &DifficultyProfile) -> Vec<Planet> {
+        let mut planets = Vec::new();
+        field.generate_chunk(chunk, difficulty, &mut planets, &mut Vec::new());
+        planets
+    }
+
+    #[test]
+    fn chunks_only_depend_on_the_seed_and_their_index() {
+        let difficulty = DifficultyProfile::default();
+        let field = PlanetField::new(7, PlanetSettings::default(), EnemySettings::default());
+        let chunks: Vec<i64> = (-20..=0).collect();
+        let first: Vec<_> = chunks
+            .iter()
+            .map(|&chunk| planet_bits(&generate(&field, chunk, &difficulty)))
+            .collect();
+        // again, the other way around and with another field of the same seed
+        let other_field = PlanetField::new(7, PlanetSettings::default(), EnemySettings::default());
+        let mut second: Vec<_> = chunks
+            .iter()
+            .rev()
+            .map(|&chunk| planet_bits(&generate(&other_field, chunk, &difficulty)))
+            .collect();
+        second.reverse();
+        assert_eq!(first, second);
+        assert!(first.iter().any(|planets| !planets.is_empty()));
+
+        let other_seed = PlanetField::new(8, PlanetSettings::default(), EnemySettings::default());
+        assert_ne!(
+            first[0],
+            planet_bits(&generate(&other_seed, -20, &difficulty))
+        );
+    }
+
+    #[test]
+    fn orbiters_circle_a_planet_of_their_own_chunk() {
+        let difficulty = DifficultyProfile {
+            enemies_per_chunk: Curve::constant(3f32),
+            ..DifficultyProfile::default()
+        };
+        let mut field = PlanetField::new(3, PlanetSettings::default(), EnemySettings::default());
+        let mut planets = Vec::new();
+        let mut enemies = Vec::new();
+        field.generate_up_to(-20000f32, &difficulty, &mut planets, &mut enemies);
+        let mut orbiters = 0;
+        for enemy in &enemies {
+            if let EnemyKind::Orbiter { planet_index } = enemy.kind {
+                orbiters += 1;
+                let chunk_of = |y: f32| (y / CHUNK_HEIGHT).floor();
+                assert_eq!(
+                    chunk_of(planets[planet_index].position.y),
+                    chunk_of(enemy.position.y)
+                );
+            }
+        }
+        assert!(orbiters > 0);
+    }
+}
@@ -1,78 +1,173 @@
-use std::f32::consts::PI;
-
-use macroquad::{
-    camera::Camera2D,
-    color::{Color, GREEN},
-    input::is_key_down,
-    math::Vec2,
-    shapes::draw_line,
-    window::{screen_height, screen_width},
+use macroquad::math::Vec2;
+use serde::Deserialize;
+
+use crate::{
+    collision::Body, input::Input, particle::ParticleGroup,
+    particle_controller::ParticleController, particle_effect::ParticleEffect,
+    particle_pool::ParticlePool, planet::Planet,
 };
 
-use crate::{particle_controller::ParticleController, planet::Planet};
+/// How the planet to hold on to is picked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrabMode {
+    /// The planet closest to the player, however far away.
+    #[default]
+    Nearest,
+    /// The planet the player aims at (with the mouse or a stick), within `max_grab_range`.
+    Aimed,
+}
 
-const PLAYER_COLOR: Color = Color {
-    r: 0.3,
-    g: 0.7,
-    b: 0.0,
-    a: 0.2,
-};
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerSettings {
+    pub radius: f32,
+    /// Mass per area. The same as the planets, so bumping into one feels like it weighs what it
+    /// looks like.
+    pub density: f32,
+    /// How much of its speed the player loses per second.
+    pub drag: f32,
+    /// Speed gained per second while holding on to a planet.
+    pub grab_acceleration: f32,
+    /// Speed gained per second while boosting.
+    pub boost_acceleration: f32,
+    /// How quickly the rope is reeled in and out (units per second).
+    pub reel_speed: f32,
+    /// The rope can't be reeled out further than this.
+    pub max_rope_length: f32,
+    /// 0 is a rope that doesn't stretch at all, up to 1 it gets springier.
+    pub rope_elasticity: f32,
+    pub grab_mode: GrabMode,
+    /// How far from the player a planet's surface can be to be grabbed in `GrabMode::Aimed`.
+    pub max_grab_range: f32,
+    /// Seconds between two shots.
+    pub shoot_cooldown: f32,
+    pub max_ammo: u32,
+    /// Seconds it takes to get one shot back.
+    pub ammo_refill_time: f32,
+    pub particles: ParticleEffect,
+    pub trail_particles: ParticleEffect,
+}
 
-const PLAYER_PARTICLE_COLOR: Color = Color {
-    r: 1.0,
-    g: 0.1,
-    b: 0.0,
-    a: 0.2,
-};
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            radius: 50f32,
+            density: 1f32,
+            drag: 1.2,
+            grab_acceleration: 2000f32,
+            boost_acceleration: 800f32,
+            reel_speed: 400f32,
+            max_rope_length: 2000f32,
+            rope_elasticity: 0f32,
+            grab_mode: GrabMode::Nearest,
+            max_grab_range: 1500f32,
+            shoot_cooldown: 0.2,
+            max_ammo: 12,
+            ammo_refill_time: 1.5,
+            particles: ParticleEffect {
+                time_per_particle: 0.005,
+                color: [0.3, 0.7, 0.0, 0.2],
+                end_color: Some([0.8, 0.7, 0.0, 0.7]),
+                ..ParticleEffect::default()
+            },
+            trail_particles: ParticleEffect {
+                time_per_particle: 0.01,
+                lifespan: 1.0,
+                velocity_per_size: 0.5,
+                radius_per_size: 0.2,
+                color: [1.0, 0.1, 0.0, 0.2],
+                end_color: Some([0.0, 0.1, 0.0, 0.2]),
+                ..ParticleEffect::default()
+            },
+        }
+    }
+}
 
 pub struct Player {
     pub position: Vec2,
+    pub previous_position: Vec2,
     pub velocity: Vec2,
     pub radius: f32,
+    pub mass: f32,
     pub linked_planet_index: Option<usize>,
+    /// How far from the linked planet's center the rope lets the player get.
+    pub rope_length: f32,
     pub is_dead: bool,
+    pub ammo: u32,
+    /// Seconds until the next shot can be fired.
+    pub shoot_cooldown: f32,
+    /// Seconds until the next shot is refilled.
+    pub ammo_refill_timer: f32,
     pub particle_controller: ParticleController,
     pub particle_controller_trails: ParticleController,
+    pub settings: PlayerSettings,
 }
 
 impl Player {
-    pub fn new(radius: f32) -> Self {
+    pub fn new(settings: PlayerSettings) -> Self {
+        let radius = settings.radius;
         Self {
             radius,
+            mass: settings.density * radius * radius,
             position: Vec2::default(),
+            previous_position: Vec2::default(),
             velocity: Vec2::default(),
             linked_planet_index: None,
+            rope_length: 0f32,
             is_dead: false,
-            particle_controller: ParticleController::new(
-                0.005,
-                radius * 1.2,
-                radius * 0.4,
-                PLAYER_COLOR,
-                0.5,
-            ),
-            particle_controller_trails: ParticleController::new(
-                0.01,
-                radius * 0.5,
-                radius * 0.2,
-                PLAYER_PARTICLE_COLOR,
-                1.0,
-            ),
+            ammo: settings.max_ammo,
+            shoot_cooldown: 0f32,
+            ammo_refill_timer: settings.ammo_refill_time,
+            particle_controller: settings.particles.controller(radius, ParticleGroup::Player),
+            particle_controller_trails: settings
+                .trail_particles
+                .controller(radius, ParticleGroup::PlayerTrail),
+            settings,
         }
     }
 
-    pub fn update(self: &mut Self, planets: &Vec<Planet>, delta_time: f32) {
-        self.particle_controller.update(delta_time, self.position);
+    /// Switches to new tuning in the middle of a run.
+    pub fn apply_settings(self: &mut Self, settings: PlayerSettings) {
+        self.radius = settings.radius;
+        self.mass = settings.density * settings.radius * settings.radius;
+        self.ammo = self.ammo.min(settings.max_ammo);
+        self.shoot_cooldown = self.shoot_cooldown.min(settings.shoot_cooldown);
+        self.ammo_refill_timer = self.ammo_refill_timer.min(settings.ammo_refill_time);
+        settings
+            .particles
+            .apply_to(&mut self.particle_controller, settings.radius);
+        settings
+            .trail_particles
+            .apply_to(&mut self.particle_controller_trails, settings.radius);
+        self.settings = settings;
+    }
+
+    pub fn update(
+        self: &mut Self,
+        planets: &[Planet],
+        particles: &mut ParticlePool,
+        input: &Input,
+        delta_time: f32,
+    ) {
         self.particle_controller
-            .shift_color(delta_time, 0f32, 0f32, delta_time);
+            .update(particles, delta_time, self.position);
         self.particle_controller_trails
-        .update(delta_time, self.position);
-        self.particle_controller_trails
-            .shift_color(-delta_time, 0f32, 0f32, 0f32);
+            .update(particles, delta_time, self.position);
 
         if self.is_dead {
             return;
         }
 
+        self.shoot_cooldown = (self.shoot_cooldown - delta_time).max(0f32);
+        if self.ammo < self.settings.max_ammo {
+            self.ammo_refill_timer -= delta_time;
+            if self.ammo_refill_timer <= 0f32 {
+                self.ammo += 1;
+                self.ammo_refill_timer += self.settings.ammo_refill_time;
+            }
+        }
+
         let mut abs_velocity =
             f32::sqrt(self.velocity.y * self.velocity.y + self.velocity.x * self.velocity.x);
         if abs_velocity < 0.0001 {
@@ -80,11 +175,10 @@ impl Player {
             abs_velocity = 0.0001;
         }
 
-        let velocity_factor = 1f32
-            + (-1.2f32
-                + f32::from(is_key_down(macroquad::input::KeyCode::Space)) * 2000f32
-                    / abs_velocity)
-                * delta_time;
+        let acceleration = f32::from(input.grab_down) * self.settings.grab_acceleration
+            + f32::from(input.boost_down) * self.settings.boost_acceleration;
+        let velocity_factor =
+            1f32 + (-self.settings.drag + acceleration / abs_velocity) * delta_time;
 
         self.velocity.x *= velocity_factor;
         self.velocity.y *= velocity_factor;
@@ -103,147 +197,87 @@ impl Player {
         //     self.velocity.y = -50f32;
         // }
 
-        match self.linked_planet_index {
-            Some(linked_planet_index) => {
-                let linked_planet: &Planet = &planets[linked_planet_index];
-
-                let to_planet = linked_planet.position - self.position;
-
-                let mut angle_diff =
-                    (to_planet.y.atan2(to_planet.x) - self.velocity.y.atan2(self.velocity.x)) % PI;
-                if angle_diff > 0.0 {
-                    angle_diff -= 0.5 * PI;
-                } else {
-                    angle_diff += 0.5 * PI;
-                };
-
-                let max_rotation = 6.0 * delta_time; // rotation per second
-                let rotation_angle = angle_diff.clamp(-max_rotation, max_rotation);
-
-                // rotate velocity:
-                let sin_angle = rotation_angle.sin();
-                let cos_angle = rotation_angle.cos();
-                let old_vel_x = self.velocity.x;
-                self.velocity.x = old_vel_x * cos_angle - self.velocity.y * sin_angle;
-                self.velocity.y = old_vel_x * sin_angle + self.velocity.y * cos_angle;
-
-                // correcting so that you don't drift outward (no clue why it doesn't work properly)
-                self.velocity += to_planet.normalize()
-                    * (1f32
-                        - f32::cos(f32::asin(
-                            (self.velocity.length() * delta_time / to_planet.length()).min(1f32),
-                        )))
-                    / delta_time;
-
-                // 'not so clean linking solution'™
-                let change = linked_planet.velocity * delta_time;
-                self.position += change;
-                self.particle_controller.inherit_movement(change);
-            }
-            None => {}
+        if let Some(linked_planet_index) = self.linked_planet_index {
+            self.update_rope(&planets[linked_planet_index], particles, input, delta_time);
+            return;
         }
 
         // position
         let change = self.velocity * delta_time;
         self.position += change;
-        self.particle_controller.inherit_movement(change);
+        self.particle_controller.inherit_movement(particles, change);
     }
 
-    pub fn draw(self: &Self, planets: &Vec<Planet>, camera: &Camera2D) {
-        match self.linked_planet_index {
-            Some(linked_planet_index) => {
-                let linked_planet_position = planets[linked_planet_index].position;
-                draw_line(
-                    self.position.x - camera.target.x + camera.offset.x,
-                    self.position.y - camera.target.y + camera.offset.y,
-                    linked_planet_position.x - camera.target.x + camera.offset.x,
-                    linked_planet_position.y - camera.target.y + camera.offset.y,
-                    10f32,
-                    GREEN,
-                );
-            }
-            None => {}
-        }
-        self.particle_controller_trails.draw(camera);
-        self.particle_controller.draw(camera);
+    /// Holds on to `planets[planet_index]` with a rope as long as the distance to it right now.
+    pub fn grab(self: &mut Self, planet_index: usize, planets: &[Planet]) {
+        self.linked_planet_index = Some(planet_index);
+        self.rope_length = (planets[planet_index].position - self.position).length();
     }
 
-    pub fn let_go_of_planet(self: &mut Self, planets: &Vec<Planet>) {
-        match self.linked_planet_index {
-            Some(linked_planet_index) => {
-                let linked_planet = &planets[linked_planet_index];
-                self.velocity += linked_planet.velocity;
-
-                self.linked_planet_index = None;
-            }
-            None => {}
+    // Position based dynamics: move freely, then pull the position back so the rope to where
+    // the planet will be after this step isn't longer than it is, and take the velocity from
+    // how far the player actually moved. Planets move after the player in a step.
+    fn update_rope(
+        self: &mut Self,
+        planet: &Planet,
+        particles: &mut ParticlePool,
+        input: &Input,
+        delta_time: f32,
+    ) {
+        let min_length = planet.radius + self.radius;
+        // a rope grabbed (or tuned) longer than the max can be reeled in, but not out any further
+        let max_length = self
+            .settings
+            .max_rope_length
+            .max(self.rope_length)
+            .max(min_length);
+        let reel = f32::from(input.reel_out) - f32::from(input.reel_in);
+        self.rope_length = (self.rope_length + reel * self.settings.reel_speed * delta_time)
+            .clamp(min_length, max_length);
+
+        let planet_position = planet.position + planet.velocity * delta_time;
+        let mut offset = self.position + self.velocity * delta_time - planet_position;
+        let distance = offset.length();
+        // a rope only pulls, it goes slack when the player comes closer
+        if distance > self.rope_length {
+            let correction = offset * (self.rope_length / distance - 1f32);
+            offset += correction * (1f32 - self.settings.rope_elasticity);
         }
+
+        let change = planet_position + offset - self.position;
+        self.velocity = change / delta_time;
+        self.position += change;
+        self.particle_controller.inherit_movement(particles, change);
     }
 
-    pub fn update_camera(self: &Self, camera: &mut Camera2D) {
-        camera.offset = Vec2 {
-            x: screen_width() * 0.5f32 - self.velocity.x * 0.02f32,
-            y: screen_height() * 0.5f32 - self.velocity.y * 0.02f32,
-        };
+    pub fn can_shoot(self: &Self) -> bool {
+        !self.is_dead && self.ammo > 0 && self.shoot_cooldown <= 0f32
+    }
 
-        camera.target = Vec2 {
-            x: self.position.x,
-            y: self.position.y,
+    /// Uses up a shot and returns the direction it goes in: toward `aim` if there is one,
+    /// otherwise the direction the player is flying (or up, when standing still).
+    pub fn shoot(self: &mut Self, aim: Option<Vec2>) -> Vec2 {
+        self.ammo -= 1;
+        self.shoot_cooldown = self.settings.shoot_cooldown;
+        let direction = match aim {
+            Some(aim) => aim - self.position,
+            None => self.velocity,
         };
+        direction
+            .try_normalize()
+            .unwrap_or(Vec2 { x: 0f32, y: -1f32 })
     }
 
-    pub fn handle_collistion(self: &mut Self, other: &mut Planet) {
-        // position
-        let angle: f32 =
-            -(self.position.y - other.position.y).atan2(self.position.x - other.position.x);
-        let sin_angle = angle.sin();
-        let cos_angle = angle.cos();
-
-        let mut old_pos_x = self.position.x;
-        self.position.x = old_pos_x * cos_angle - self.position.y * sin_angle;
-        self.position.y = old_pos_x * sin_angle + self.position.y * cos_angle;
-
-        old_pos_x = other.position.x;
-        other.position.x = old_pos_x * cos_angle - other.position.y * sin_angle;
-        other.position.y = old_pos_x * sin_angle + other.position.y * cos_angle;
-
-        let dist_per_mass: f32 =
-            (-(self.position.x - other.position.x).abs() + self.radius + other.radius) / 2f32; // / (mass + e.mass);
-        self.position.x += dist_per_mass; // * e.mass;
-        other.position.x -= dist_per_mass; // * mass;
-
-        old_pos_x = self.position.x;
-        self.position.x = old_pos_x * cos_angle + self.position.y * sin_angle;
-        self.position.y = old_pos_x * (-sin_angle) + self.position.y * cos_angle;
-
-        old_pos_x = other.position.x;
-        other.position.x = old_pos_x * cos_angle + other.position.y * sin_angle;
-        other.position.y = old_pos_x * (-sin_angle) + other.position.y * cos_angle;
-
-        // velocity
-        let mut old_vel_x = self.velocity.x;
-        self.velocity.x = old_vel_x * cos_angle - self.velocity.y * sin_angle;
-        self.velocity.y = old_vel_x * sin_angle + self.velocity.y * cos_angle;
-
-        old_vel_x = other.velocity.x;
-        other.velocity.x = old_vel_x * cos_angle - other.velocity.y * sin_angle;
-        other.velocity.y = old_vel_x * sin_angle + other.velocity.y * cos_angle;
-
-        // let totalMass: f32 = mass + e.mass;
-        // let factor: f32 = (mass - e.mass) / totalMass;
-        // let prevVel: f32 = self.velocity.x;
-        // vel.x = factor * vel.x + 2 * e.vel.x * e.mass / totalMass;
-        // e.vel.x = 2 * prevVel * mass / totalMass - factor * e.vel.x;
-        let prev_vel: f32 = self.velocity.x;
-        self.velocity.x = other.velocity.x;
-        other.velocity.x = prev_vel;
-
-        old_vel_x = self.velocity.x;
-        self.velocity.x = old_vel_x * cos_angle + self.velocity.y * sin_angle;
-        self.velocity.y = old_vel_x * (-sin_angle) + self.velocity.y * cos_angle;
-
-        old_vel_x = other.velocity.x;
-        other.velocity.x = old_vel_x * cos_angle + other.velocity.y * sin_angle;
-        other.velocity.y = old_vel_x * (-sin_angle) + other.velocity.y * cos_angle;
+    pub fn let_go_of_planet(self: &mut Self) {
+        self.linked_planet_index = None;
+    }
+
+    pub fn body(self: &mut Self) -> Body<'_> {
+        Body {
+            position: &mut self.position,
+            velocity: &mut self.velocity,
+            radius: self.radius,
+            mass: self.mass,
+        }
     }
 }
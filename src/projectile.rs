@@ -0,0 +1,87 @@
+use macroquad::{color::Color, math::Vec2};
+use serde::Deserialize;
+
+use crate::particle::ParticleGroup;
+use crate::particle_controller::ParticleController;
+use crate::particle_effect::ParticleEffect;
+use crate::particle_pool::ParticlePool;
+
+/// Speed of a shot relative to the player that fired it.
+pub const PROJECTILE_SPEED: f32 = 1400f32;
+pub const PROJECTILE_RADIUS: f32 = 8f32;
+pub const PROJECTILE_MASS: f32 = 400f32;
+/// Seconds before a shot that didn't hit anything disappears.
+pub const PROJECTILE_LIFETIME: f32 = 2f32;
+
+const PROJECTILE_COLOR: Color = Color {
+    r: 1.0,
+    g: 0.8,
+    b: 0.2,
+    a: 0.9,
+};
+
+/// What shots look like. Trail particle speed and radius are relative to `PROJECTILE_RADIUS`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectileSettings {
+    pub trail_particles: ParticleEffect,
+}
+
+impl Default for ProjectileSettings {
+    fn default() -> Self {
+        Self {
+            trail_particles: ParticleEffect {
+                lifespan: 0.4,
+                velocity_per_size: 2.0,
+                radius_per_size: 0.6,
+                color: [1.0, 0.5, 0.1, 0.4],
+                end_color: Some([1.0, 0.1, 0.1, 0.4]),
+                ..ParticleEffect::default()
+            },
+        }
+    }
+}
+
+pub struct Projectile {
+    pub position: Vec2,
+    pub previous_position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f32,
+    pub mass: f32,
+    pub color: Color,
+    pub time_left: f32,
+    pub particle_controller_trails: ParticleController,
+}
+
+impl Projectile {
+    pub fn new(position: Vec2, velocity: Vec2, settings: &ProjectileSettings) -> Self {
+        Self {
+            position,
+            previous_position: position,
+            velocity,
+            radius: PROJECTILE_RADIUS,
+            mass: PROJECTILE_MASS,
+            color: PROJECTILE_COLOR,
+            time_left: PROJECTILE_LIFETIME,
+            particle_controller_trails: settings
+                .trail_particles
+                .controller(PROJECTILE_RADIUS, ParticleGroup::Projectile),
+        }
+    }
+
+    pub fn update(self: &mut Self, particles: &mut ParticlePool, delta_time: f32) {
+        self.time_left -= delta_time;
+        self.position += self.velocity * delta_time;
+        self.particle_controller_trails
+            .update(particles, delta_time, self.position);
+    }
+
+    pub fn is_expired(self: &Self) -> bool {
+        self.time_left <= 0f32
+    }
+
+    /// Whether the shot touches a circle, e.g. a planet or an enemy.
+    pub fn hits(self: &Self, position: Vec2, radius: f32) -> bool {
+        (self.position - position).length() < self.radius + radius
+    }
+}
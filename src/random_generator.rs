@@ -1,14 +1,34 @@
 use macroquad::rand::RandGenerator;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn get_rand_generator() -> RandGenerator {
+pub fn get_rand_generator(seed: u64) -> RandGenerator {
     // random number generator
     let rng = RandGenerator::new();
-    // this is the seed of the number generator (the current time in milliseconds)
+    rng.srand(seed);
+    rng
+}
+
+/// Seed for runs that don't need to be reproducible (the current time in milliseconds).
+pub fn get_time_seed() -> u64 {
     let current_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("yooo, the time is all fucked up 'n shit")
         .as_millis();
-    rng.srand(current_time as u64);
-    rng
+    current_time as u64
+}
+
+/// Derives a seed for a child generator, so every entity gets its own stream of numbers that
+/// still only depends on the seed of `rng`.
+pub fn next_seed(rng: &RandGenerator) -> u64 {
+    ((rng.rand() as u64) << 32) | rng.rand() as u64
+}
+
+/// Seed for the `index`-th part of something seeded with `seed` (e.g. a chunk of the world).
+/// Neighbouring indices get unrelated seeds, so their random numbers don't look alike.
+pub fn indexed_seed(seed: u64, index: i64) -> u64 {
+    // splitmix64
+    let mut z = seed.wrapping_add((index as u64).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
 }
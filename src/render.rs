@@ -0,0 +1,384 @@
+use macroquad::color::{Color, BLACK, GREEN, LIGHTGRAY, RED, WHITE, YELLOW};
+use macroquad::math::{Rect, Vec2};
+use macroquad::models::{draw_mesh, Mesh, Vertex};
+use macroquad::shapes::{draw_circle, draw_circle_lines, draw_line, draw_rectangle};
+use macroquad::text::{draw_text, measure_text};
+use macroquad::texture::{draw_texture_ex, DrawTextureParams, Texture2D};
+use macroquad::window::{clear_background, screen_height, screen_width};
+use serde::Deserialize;
+
+use crate::assets::{Assets, Sprite, DANGER_ZONE_SPRITE, PLANET_SPRITE, PLAYER_SPRITE};
+use crate::camera::GameCamera;
+use crate::controls::{Action, Bindings};
+use crate::danger_zone::DangerZone;
+use crate::high_scores::HighScoreTable;
+use crate::particle::{Particle, ParticleGroup, NO_ANCHOR};
+use crate::particle_pool::ParticlePool;
+use crate::planet::Planet;
+use crate::player::{GrabMode, Player};
+use crate::projectile::Projectile;
+use crate::world::World;
+
+// macroquad draws at most 10000 vertices and 5000 indices at once, a particle takes 4 and 6
+const PARTICLES_PER_MESH: usize = 800;
+
+// The simulation runs at a fixed timestep, so a frame is usually drawn somewhere between two
+// steps. `alpha` (0 to 1) says where, and everything is shifted from its current position
+// toward where it was at the previous step accordingly.
+fn interpolation_shift(previous_position: Vec2, position: Vec2, alpha: f32) -> Vec2 {
+    previous_position.lerp(position, alpha) - position
+}
+
+/// How things are drawn, only for looks.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderSettings {
+    /// Keep drawing the particle clouds on top of sprites, instead of only the sprites.
+    pub particles_with_sprites: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            particles_with_sprites: true,
+        }
+    }
+}
+
+pub fn draw_world(
+    world: &World,
+    alpha: f32,
+    camera: &GameCamera,
+    assets: &Assets,
+    settings: &RenderSettings,
+    particle_mesh: Option<&mut Mesh>,
+) {
+    clear_background(BLACK);
+
+    camera.begin_world();
+    draw_player(&world.player, &world.planets, alpha, assets);
+    draw_danger_zone(&world.bottom_danger_zone, alpha, assets);
+    if !world.player.is_dead {
+        draw_danger_zone(&world.left_danger_zone, alpha, assets);
+        draw_danger_zone(&world.right_danger_zone, alpha, assets);
+    }
+    for planet in &world.planets {
+        draw_planet(planet, alpha, assets);
+    }
+    // particle clouds go on top of the sprites, and are all that's drawn of enemies. Which
+    // groups are drawn is looked up once here, not for every particle
+    let is_player_drawn = draws_particles(assets.sprite(PLAYER_SPRITE), settings);
+    let is_planet_drawn = draws_particles(assets.sprite(PLANET_SPRITE), settings);
+    let is_bottom_zone_drawn = draws_particles(assets.sprite(DANGER_ZONE_SPRITE), settings);
+    let is_side_zone_drawn = is_bottom_zone_drawn && !world.player.is_dead;
+    draw_particles(
+        &world.particles,
+        alpha,
+        camera.visible_area(),
+        particle_mesh,
+        |group| match group {
+            ParticleGroup::Player => is_player_drawn,
+            ParticleGroup::Planet => is_planet_drawn,
+            ParticleGroup::BottomZone => is_bottom_zone_drawn,
+            ParticleGroup::SideZone => is_side_zone_drawn,
+            ParticleGroup::PlayerTrail | ParticleGroup::Enemy | ParticleGroup::Projectile => true,
+        },
+    );
+    if let Some(index) = world.grab_candidate {
+        draw_grab_indicator(&world.planets[index], alpha);
+    }
+    for projectile in &world.projectiles {
+        draw_projectile(projectile, alpha);
+    }
+    camera.end_world();
+}
+
+// `y` is relative to the middle of the screen
+fn draw_centered_text(text: &str, y: f32, font_size: f32, color: Color) {
+    let size = measure_text(text, None, font_size as u16, 1f32);
+    draw_text(
+        text,
+        (screen_width() - size.width) * 0.5f32,
+        screen_height() * 0.5f32 + y + size.offset_y * 0.5f32,
+        font_size,
+        color,
+    );
+}
+
+// darkens whatever is behind a menu
+fn draw_screen_overlay() {
+    draw_rectangle(
+        0f32,
+        0f32,
+        screen_width(),
+        screen_height(),
+        Color::new(0f32, 0f32, 0f32, 0.6),
+    );
+}
+
+pub fn draw_title_screen(bindings: &Bindings, grab_mode: GrabMode) {
+    draw_screen_overlay();
+    draw_centered_text("DAVITRON", -60f32, 100f32, WHITE);
+    let planet = match grab_mode {
+        GrabMode::Nearest => "the nearest planet",
+        GrabMode::Aimed => "the planet you aim at",
+    };
+    draw_centered_text(
+        &format!("hold {} to grab {}", bindings.names(Action::Grab), planet),
+        30f32,
+        30f32,
+        LIGHTGRAY,
+    );
+    draw_centered_text(
+        &format!("press {} to start", bindings.names(Action::Grab)),
+        80f32,
+        30f32,
+        LIGHTGRAY,
+    );
+}
+
+pub fn draw_pause_screen(bindings: &Bindings) {
+    draw_screen_overlay();
+    draw_centered_text("PAUSED", -20f32, 80f32, WHITE);
+    draw_centered_text(
+        &format!("press {} to continue", bindings.names(Action::Pause)),
+        50f32,
+        30f32,
+        LIGHTGRAY,
+    );
+}
+
+/// Current and best score in the top left corner, ammo below them.
+pub fn draw_hud(score: u32, best_score: u32, ammo: u32) {
+    draw_text(&format!("SCORE {}", score), 20f32, 40f32, 40f32, WHITE);
+    draw_text(
+        &format!("BEST {}", best_score.max(score)),
+        20f32,
+        75f32,
+        30f32,
+        LIGHTGRAY,
+    );
+    draw_text(&format!("AMMO {}", ammo), 20f32, 105f32, 30f32, LIGHTGRAY);
+}
+
+/// `name_entry` is the name typed so far while the score is waiting to be put into the table.
+pub fn draw_game_over_screen(
+    score: u32,
+    high_scores: &HighScoreTable,
+    name_entry: Option<&str>,
+    bindings: &Bindings,
+) {
+    draw_screen_overlay();
+    draw_centered_text("YOU DIED LOSER", -260f32, 80f32, RED);
+    draw_centered_text(&format!("SCORE {}", score), -190f32, 40f32, WHITE);
+
+    match name_entry {
+        Some(name) => {
+            draw_centered_text(
+                "NEW HIGH SCORE! enter your name:",
+                -120f32,
+                30f32,
+                LIGHTGRAY,
+            );
+            draw_centered_text(&format!("{}_", name), -70f32, 50f32, YELLOW);
+            draw_centered_text("press ENTER when done", -20f32, 30f32, LIGHTGRAY);
+        }
+        None => {
+            for (place, entry) in high_scores.entries.iter().enumerate() {
+                draw_centered_text(
+                    &format!("{:>2}. {:<12} {:>8}", place + 1, entry.name, entry.score),
+                    -120f32 + place as f32 * 32f32,
+                    30f32,
+                    WHITE,
+                );
+            }
+            draw_centered_text(
+                &format!("press {} to try again", bindings.names(Action::Confirm)),
+                240f32,
+                30f32,
+                LIGHTGRAY,
+            );
+        }
+    }
+}
+
+// Draws `sprite` centered on `position`, stretched to `size` (world units) and turned by
+// `rotation` radians around its center.
+fn draw_sprite(sprite: &Sprite, position: Vec2, size: Vec2, rotation: f32) {
+    draw_texture_ex(
+        &sprite.texture,
+        position.x - size.x * 0.5,
+        position.y - size.y * 0.5,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(size),
+            source: Some(sprite.source),
+            rotation,
+            ..Default::default()
+        },
+    );
+}
+
+// the particle clouds are what things look like without sprites
+fn draws_particles(sprite: Option<&Sprite>, settings: &RenderSettings) -> bool {
+    sprite.is_none() || settings.particles_with_sprites
+}
+
+pub fn draw_player(player: &Player, planets: &[Planet], alpha: f32, assets: &Assets) {
+    let shift = interpolation_shift(player.previous_position, player.position, alpha);
+    if let Some(linked_planet_index) = player.linked_planet_index {
+        let linked_planet = &planets[linked_planet_index];
+        let player_position = player.position + shift;
+        let linked_planet_position = linked_planet.position
+            + interpolation_shift(
+                linked_planet.previous_position,
+                linked_planet.position,
+                alpha,
+            );
+        // a slack rope is drawn thinner than a taut one
+        let is_taut =
+            (player.position - linked_planet.position).length() >= player.rope_length - 1f32;
+        draw_line(
+            player_position.x,
+            player_position.y,
+            linked_planet_position.x,
+            linked_planet_position.y,
+            if is_taut { 10f32 } else { 4f32 },
+            GREEN,
+        );
+    }
+    if let Some(sprite) = assets.sprite(PLAYER_SPRITE) {
+        // facing where it's flying
+        draw_sprite(
+            sprite,
+            player.position + shift,
+            Vec2::splat(player.radius * 2f32),
+            player.velocity.y.atan2(player.velocity.x),
+        );
+    }
+}
+
+pub fn draw_projectile(projectile: &Projectile, alpha: f32) {
+    let position = projectile
+        .previous_position
+        .lerp(projectile.position, alpha);
+    draw_circle(position.x, position.y, projectile.radius, projectile.color);
+}
+
+pub fn draw_planet(planet: &Planet, alpha: f32, assets: &Assets) {
+    let shift = interpolation_shift(planet.previous_position, planet.position, alpha);
+    if let Some(sprite) = assets.sprite(PLANET_SPRITE) {
+        draw_sprite(
+            sprite,
+            planet.position + shift,
+            Vec2::splat(planet.radius * 2f32),
+            planet.rotation,
+        );
+    }
+}
+
+/// A ring around the planet grabbing would hold on to.
+pub fn draw_grab_indicator(planet: &Planet, alpha: f32) {
+    let position = planet.previous_position.lerp(planet.position, alpha);
+    draw_circle_lines(
+        position.x,
+        position.y,
+        planet.radius * 1.5 + 20f32,
+        6f32,
+        Color::new(0.0, 0.9, 0.0, 0.5),
+    );
+}
+
+pub fn draw_danger_zone(danger_zone: &DangerZone, alpha: f32, assets: &Assets) {
+    let shift = interpolation_shift(danger_zone.previous_position, danger_zone.position, alpha);
+    if let Some(sprite) = assets.sprite(DANGER_ZONE_SPRITE) {
+        draw_sprite(sprite, danger_zone.position + shift, danger_zone.size, 0f32);
+    }
+}
+
+/// An empty mesh drawn with `texture`, with room for as many particles as `draw_particles` draws
+/// at once. Made once and reused every frame, so drawing particles doesn't allocate.
+pub fn particle_mesh(texture: &Texture2D) -> Mesh {
+    Mesh {
+        vertices: Vec::with_capacity(PARTICLES_PER_MESH * 4),
+        indices: Vec::with_capacity(PARTICLES_PER_MESH * 6),
+        texture: Some(texture.clone()),
+    }
+}
+
+/// Draws every particle in `view` of a group `is_drawn` says yes to. Particles that move along
+/// with something are interpolated like it, the others are drawn where they are. With a `mesh`
+/// (see `particle_mesh`), particles are drawn as textured squares gathered into as few draw
+/// calls as they fit into, instead of a circle each.
+pub fn draw_particles(
+    pool: &ParticlePool,
+    alpha: f32,
+    view: Rect,
+    mut mesh: Option<&mut Mesh>,
+    is_drawn: impl Fn(ParticleGroup) -> bool,
+) {
+    if let Some(mesh) = &mut mesh {
+        mesh.vertices.clear();
+        mesh.indices.clear();
+    }
+    for particle in pool.particles() {
+        if !is_drawn(particle.group) {
+            continue;
+        }
+        let shift = if particle.anchor == NO_ANCHOR {
+            Vec2::ZERO
+        } else {
+            interpolation_shift(Vec2::ZERO, pool.anchor_movement(particle.anchor), alpha)
+        };
+        let position = particle.position + shift;
+        if position.x + particle.radius < view.left()
+            || position.x - particle.radius > view.right()
+            || position.y + particle.radius < view.top()
+            || position.y - particle.radius > view.bottom()
+        {
+            continue;
+        }
+        match mesh.as_deref_mut() {
+            Some(mesh) => {
+                if mesh.vertices.len() >= PARTICLES_PER_MESH * 4 {
+                    draw_mesh(mesh);
+                    mesh.vertices.clear();
+                    mesh.indices.clear();
+                }
+                add_particle_quad(mesh, position, particle.radius, particle.color);
+            }
+            None => draw_particle(particle, shift),
+        }
+    }
+    if let Some(mesh) = mesh {
+        if !mesh.vertices.is_empty() {
+            draw_mesh(mesh);
+        }
+    }
+}
+
+// two triangles covering the particle's circle, the texture makes it round
+fn add_particle_quad(mesh: &mut Mesh, position: Vec2, radius: f32, color: Color) {
+    let first = mesh.vertices.len() as u16;
+    for (u, v) in [(0f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0f32, 1f32)] {
+        mesh.vertices.push(Vertex::new(
+            position.x + (u * 2f32 - 1f32) * radius,
+            position.y + (v * 2f32 - 1f32) * radius,
+            0f32,
+            u,
+            v,
+            color,
+        ));
+    }
+    mesh.indices
+        .extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+}
+
+pub fn draw_particle(particle: &Particle, shift: Vec2) {
+    draw_circle(
+        particle.position.x + shift.x,
+        particle.position.y + shift.y,
+        particle.radius,
+        particle.color,
+    );
+}
@@ -0,0 +1,277 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use macroquad::math::Vec2;
+
+use crate::config::Config;
+use crate::input::Input;
+
+const MAGIC: &[u8; 4] = b"DVRP";
+const VERSION: u8 = 7;
+const HEADER_SIZE: usize = 4 + 1 + 8 + 8 + 1;
+// frame time and input flags, the aim position is only stored when there is one
+const FRAME_SIZE: usize = 4 + 1;
+const AIM_SIZE: usize = 4 + 4;
+
+const GRAB_PRESSED: u8 = 1 << 0;
+const GRAB_RELEASED: u8 = 1 << 1;
+const GRAB_DOWN: u8 = 1 << 2;
+const SHOOT_DOWN: u8 = 1 << 3;
+const HAS_AIM: u8 = 1 << 4;
+const BOOST_DOWN: u8 = 1 << 5;
+const REEL_IN: u8 = 1 << 6;
+const REEL_OUT: u8 = 1 << 7;
+
+const GRAVITY_ENABLED: u8 = 1 << 0;
+
+/// What the game loop read in one rendered frame: how long the frame took and the player's
+/// input during it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub frame_time: f32,
+    pub input: Input,
+}
+
+/// Everything needed to play a run again exactly: the seed and settings the `World` was built
+/// with, and every frame's delta time and input. The config isn't stored, only its
+/// `Config::simulation_hash`, so playing back with a different one can be refused.
+///
+/// File layout (little endian): `DVRP`, version (u8), seed (u64), config hash (u64), world
+/// settings as bit flags (u8), then per frame: frame time (f32), the input as bit flags (u8)
+/// and, only if the `HAS_AIM` flag is set, the aim position (2 x f32).
+pub struct Replay {
+    pub seed: u64,
+    pub config_hash: u64,
+    pub gravity_enabled: bool,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: &Config, gravity_enabled: bool) -> Self {
+        Self {
+            seed,
+            config_hash: config.simulation_hash(),
+            gravity_enabled,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Fails if `config` isn't the one the replay was recorded with, it would play back
+    /// differently.
+    pub fn check_config(self: &Self, config: &Config) -> Result<()> {
+        if config.simulation_hash() != self.config_hash {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "the replay was recorded with a different config, it would play back differently",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn record(self: &mut Self, frame_time: f32, input: Input) {
+        self.frames.push(ReplayFrame { frame_time, input });
+    }
+
+    pub fn save(self: &Self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn to_bytes(self: &Self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.frames.len() * FRAME_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.config_hash.to_le_bytes());
+        bytes.push(if self.gravity_enabled {
+            GRAVITY_ENABLED
+        } else {
+            0
+        });
+        for frame in &self.frames {
+            bytes.extend_from_slice(&frame.frame_time.to_le_bytes());
+            let mut flags = 0u8;
+            if frame.input.grab_pressed {
+                flags |= GRAB_PRESSED;
+            }
+            if frame.input.grab_released {
+                flags |= GRAB_RELEASED;
+            }
+            if frame.input.grab_down {
+                flags |= GRAB_DOWN;
+            }
+            if frame.input.boost_down {
+                flags |= BOOST_DOWN;
+            }
+            if frame.input.reel_in {
+                flags |= REEL_IN;
+            }
+            if frame.input.reel_out {
+                flags |= REEL_OUT;
+            }
+            if frame.input.shoot_down {
+                flags |= SHOOT_DOWN;
+            }
+            if frame.input.aim.is_some() {
+                flags |= HAS_AIM;
+            }
+            bytes.push(flags);
+            if let Some(aim) = frame.input.aim {
+                bytes.extend_from_slice(&aim.x.to_le_bytes());
+                bytes.extend_from_slice(&aim.y.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a replay file"));
+        }
+        if bytes[4] != VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported replay version {}", bytes[4]),
+            ));
+        }
+        let truncated = || Error::new(ErrorKind::InvalidData, "replay file is truncated");
+        let read_f32 = |at: usize| -> Result<f32> {
+            let field = bytes.get(at..at + 4).ok_or_else(truncated)?;
+            Ok(f32::from_le_bytes(field.try_into().unwrap()))
+        };
+
+        let mut replay = Self {
+            seed: u64::from_le_bytes(bytes[5..13].try_into().unwrap()),
+            config_hash: u64::from_le_bytes(bytes[13..21].try_into().unwrap()),
+            gravity_enabled: bytes[21] & GRAVITY_ENABLED != 0,
+            frames: Vec::new(),
+        };
+        let mut at = HEADER_SIZE;
+        while at < bytes.len() {
+            if at + FRAME_SIZE > bytes.len() {
+                return Err(truncated());
+            }
+            let frame_time = read_f32(at)?;
+            let flags = bytes[at + 4];
+            at += FRAME_SIZE;
+            let aim = if flags & HAS_AIM != 0 {
+                let aim = Vec2 {
+                    x: read_f32(at)?,
+                    y: read_f32(at + 4)?,
+                };
+                at += AIM_SIZE;
+                Some(aim)
+            } else {
+                None
+            };
+            replay.record(
+                frame_time,
+                Input {
+                    grab_pressed: flags & GRAB_PRESSED != 0,
+                    grab_released: flags & GRAB_RELEASED != 0,
+                    grab_down: flags & GRAB_DOWN != 0,
+                    boost_down: flags & BOOST_DOWN != 0,
+                    reel_in: flags & REEL_IN != 0,
+                    reel_out: flags & REEL_OUT != 0,
+                    shoot_down: flags & SHOOT_DOWN != 0,
+                    aim,
+                },
+            );
+        }
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_replay() -> Replay {
+        let mut replay = Replay::new(0x0123_4567_89ab_cdef, &Config::default(), true);
+        replay.record(1f32 / 60f32, Input::default());
+        replay.record(
+            0.02,
+            Input {
+                grab_pressed: true,
+                grab_down: true,
+                reel_in: true,
+                shoot_down: true,
+                aim: Some(Vec2::new(-12.5, 3000.25)),
+                ..Input::default()
+            },
+        );
+        replay.record(
+            0.015,
+            Input {
+                grab_released: true,
+                boost_down: true,
+                reel_out: true,
+                ..Input::default()
+            },
+        );
+        replay
+    }
+
+    #[test]
+    fn round_trips() {
+        let replay = sample_replay();
+        let loaded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.config_hash, replay.config_hash);
+        assert_eq!(loaded.gravity_enabled, replay.gravity_enabled);
+        assert_eq!(loaded.frames, replay.frames);
+    }
+
+    #[test]
+    fn stores_aim_only_when_there_is_one() {
+        let bytes = sample_replay().to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 3 * FRAME_SIZE + AIM_SIZE);
+        let flags = bytes[HEADER_SIZE + FRAME_SIZE + 4];
+        assert_ne!(flags & HAS_AIM, 0);
+        let aim_at = HEADER_SIZE + 2 * FRAME_SIZE;
+        assert_eq!(bytes[aim_at..aim_at + 4], (-12.5f32).to_le_bytes());
+        assert_eq!(bytes[aim_at + 4..aim_at + 8], 3000.25f32.to_le_bytes());
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = sample_replay().to_bytes();
+        // cut into the header, a frame and the aim position
+        let aim_at = HEADER_SIZE + 2 * FRAME_SIZE;
+        for length in [0, HEADER_SIZE - 1, HEADER_SIZE + 2, aim_at + 6] {
+            let error = Replay::from_bytes(&bytes[..length]).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "length {}", length);
+        }
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = sample_replay().to_bytes();
+        bytes[4] = VERSION - 1;
+        let error = Replay::from_bytes(&bytes).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains("version"));
+    }
+
+    #[test]
+    fn refuses_a_different_config() {
+        let bytes = sample_replay().to_bytes();
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert!(replay.check_config(&Config::default()).is_ok());
+
+        let mut config = Config::default();
+        config.zone.bottom_speed += 1f32;
+        let error = replay.check_config(&config).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        // only looks changed, it plays back the same
+        let mut config = Config::default();
+        config.planet.particles.color = [1f32, 0f32, 0f32, 1f32];
+        config.camera.max_zoom = 2f32;
+        assert!(replay.check_config(&config).is_ok());
+    }
+}
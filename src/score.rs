@@ -0,0 +1,38 @@
+use crate::difficulty::altitude;
+
+/// Altitude per point of score.
+pub const ALTITUDE_PER_POINT: f32 = 10f32;
+/// Letting go of a planet faster than this counts as a slingshot and earns a bonus.
+pub const SLINGSHOT_MIN_SPEED: f32 = 1500f32;
+/// Bonus points per unit of speed of a slingshot.
+pub const SLINGSHOT_BONUS_PER_SPEED: f32 = 0.01;
+/// Bonus points for shooting down an enemy.
+pub const ENEMY_KILL_BONUS: u32 = 50;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Score {
+    /// The highest the player has been, falling back down doesn't cost anything.
+    pub max_altitude: f32,
+    pub style_bonus: u32,
+}
+
+impl Score {
+    pub fn update_height(self: &mut Self, y: f32) {
+        self.max_altitude = self.max_altitude.max(altitude(y));
+    }
+
+    /// Called when the player lets go of a planet at `speed`.
+    pub fn add_slingshot(self: &mut Self, speed: f32) {
+        if speed > SLINGSHOT_MIN_SPEED {
+            self.style_bonus += (speed * SLINGSHOT_BONUS_PER_SPEED) as u32;
+        }
+    }
+
+    pub fn add_enemy_kill(self: &mut Self) {
+        self.style_bonus += ENEMY_KILL_BONUS;
+    }
+
+    pub fn total(self: &Self) -> u32 {
+        (self.max_altitude / ALTITUDE_PER_POINT) as u32 + self.style_bonus
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use macroquad::math::Vec2;
+
+// inclusive cell range covered by a circle's bounding box
+#[derive(Clone, Copy)]
+struct CellBounds {
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+}
+
+/// Uniform grid of square cells for finding circles near each other without comparing every
+/// circle to every other one. Circles are stored by index (e.g. into the planet list) and added
+/// to every cell their bounding box touches.
+///
+/// Positions are copied on `insert`, so the hash has to be rebuilt after the circles moved.
+pub struct SpatialHash {
+    pub cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    positions: Vec<Vec2>,
+    radii: Vec<f32>,
+    bounds: Vec<CellBounds>,
+    // union of all bounds, so searches know when to give up
+    total_bounds: Option<CellBounds>,
+}
+
+impl SpatialHash {
+    /// Works best with `cell_size` around the diameter of a typical circle.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            positions: Vec::new(),
+            radii: Vec::new(),
+            bounds: Vec::new(),
+            total_bounds: None,
+        }
+    }
+
+    pub fn clear(self: &mut Self) {
+        // keep the cell vectors around so rebuilding every step doesn't allocate, and only drop
+        // the ones that stayed empty since the last clear
+        self.cells.retain(|_, indices| {
+            let was_used = !indices.is_empty();
+            indices.clear();
+            was_used
+        });
+        self.positions.clear();
+        self.radii.clear();
+        self.bounds.clear();
+        self.total_bounds = None;
+    }
+
+    /// Clears the hash and inserts all circles, their indices being their position in the
+    /// iterator.
+    pub fn rebuild(self: &mut Self, circles: impl Iterator<Item = (Vec2, f32)>) {
+        self.clear();
+        for (position, radius) in circles {
+            self.insert(position, radius);
+        }
+    }
+
+    /// Adds a circle and returns its index.
+    pub fn insert(self: &mut Self, position: Vec2, radius: f32) -> usize {
+        let index = self.positions.len();
+        let bounds = self.cell_bounds(position, radius);
+        for x in bounds.min_x..=bounds.max_x {
+            for y in bounds.min_y..=bounds.max_y {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+        self.positions.push(position);
+        self.radii.push(radius);
+        self.bounds.push(bounds);
+        self.total_bounds = Some(match self.total_bounds {
+            Some(total) => CellBounds {
+                min_x: total.min_x.min(bounds.min_x),
+                min_y: total.min_y.min(bounds.min_y),
+                max_x: total.max_x.max(bounds.max_x),
+                max_y: total.max_y.max(bounds.max_y),
+            },
+            None => bounds,
+        });
+        index
+    }
+
+    pub fn len(self: &Self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Appends every pair of overlapping circles (lower index first) to `pairs`.
+    pub fn overlapping_pairs(self: &Self, pairs: &mut Vec<(usize, usize)>) {
+        for (&(cell_x, cell_y), indices) in &self.cells {
+            for (n, &i) in indices.iter().enumerate() {
+                for &j in &indices[n + 1..] {
+                    // two circles can share several cells, only report them in the first one
+                    let (first_x, first_y) = self.first_shared_cell(self.bounds[i], self.bounds[j]);
+                    if first_x != cell_x || first_y != cell_y {
+                        continue;
+                    }
+                    if self.overlaps(i, self.positions[j], self.radii[j]) {
+                        pairs.push((i.min(j), i.max(j)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends the index of every circle overlapping the given circle to `result`.
+    pub fn query_circle(self: &Self, center: Vec2, radius: f32, result: &mut Vec<usize>) {
+        let Some(total_bounds) = self.total_bounds else {
+            return;
+        };
+        let query_bounds = self.cell_bounds(center, radius);
+        // cells outside of the total bounds are all empty, no need to look them up
+        for x in
+            query_bounds.min_x.max(total_bounds.min_x)..=query_bounds.max_x.min(total_bounds.max_x)
+        {
+            for y in query_bounds.min_y.max(total_bounds.min_y)
+                ..=query_bounds.max_y.min(total_bounds.max_y)
+            {
+                let Some(indices) = self.cells.get(&(x, y)) else {
+                    continue;
+                };
+                for &i in indices {
+                    if self.first_shared_cell(self.bounds[i], query_bounds) != (x, y) {
+                        continue;
+                    }
+                    if self.overlaps(i, center, radius) {
+                        result.push(i);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Index of the circle whose center is nearest to `position`, searching outward so only
+    /// circles close by are looked at. `candidates` is only scratch space, passed in so it can
+    /// be reused instead of allocated on every call.
+    pub fn nearest(self: &Self, position: Vec2, candidates: &mut Vec<usize>) -> Option<usize> {
+        let total_bounds = self.total_bounds?;
+        let mut search_radius = self.cell_size;
+        loop {
+            candidates.clear();
+            self.query_circle(position, search_radius, candidates);
+
+            let mut nearest: (f32, Option<usize>) = (f32::INFINITY, None);
+            for &index in candidates.iter() {
+                let distance = self.positions[index].distance_squared(position);
+                if distance < nearest.0 {
+                    nearest = (distance, Some(index));
+                }
+            }
+            // any circle with its center closer than the nearest one found would overlap the
+            // search circle, so it would have been found too
+            if nearest.0 <= search_radius * search_radius {
+                return nearest.1;
+            }
+
+            // once the search circle contains every cell, everything has been searched already
+            let farthest_corner = Vec2 {
+                x: (position.x - total_bounds.min_x as f32 * self.cell_size)
+                    .abs()
+                    .max((position.x - (total_bounds.max_x + 1) as f32 * self.cell_size).abs()),
+                y: (position.y - total_bounds.min_y as f32 * self.cell_size)
+                    .abs()
+                    .max((position.y - (total_bounds.max_y + 1) as f32 * self.cell_size).abs()),
+            };
+            if farthest_corner.length() <= search_radius {
+                return nearest.1;
+            }
+            search_radius *= 2f32;
+        }
+    }
+
+    fn overlaps(self: &Self, index: usize, center: Vec2, radius: f32) -> bool {
+        let reach = self.radii[index] + radius;
+        self.positions[index].distance_squared(center) < reach * reach
+    }
+
+    fn cell_bounds(self: &Self, position: Vec2, radius: f32) -> CellBounds {
+        CellBounds {
+            min_x: ((position.x - radius) / self.cell_size).floor() as i32,
+            min_y: ((position.y - radius) / self.cell_size).floor() as i32,
+            max_x: ((position.x + radius) / self.cell_size).floor() as i32,
+            max_y: ((position.y + radius) / self.cell_size).floor() as i32,
+        }
+    }
+
+    fn first_shared_cell(self: &Self, a: CellBounds, b: CellBounds) -> (i32, i32) {
+        (a.min_x.max(b.min_x), a.min_y.max(b.min_y))
+    }
+}
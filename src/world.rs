@@ -0,0 +1,734 @@
+use macroquad::math::Vec2;
+use macroquad::rand::RandGenerator;
+
+use crate::collision::{resolve_collision, CollisionSettings};
+use crate::config::Config;
+use crate::danger_zone::{DangerZone, ZoneSettings};
+use crate::difficulty::{altitude, DifficultyProfile};
+use crate::enemy::{Enemy, EnemyKind};
+use crate::gravity::apply_gravity;
+use crate::input::Input;
+use crate::particle::ParticleGroup;
+use crate::particle_pool::{ParticlePool, MAX_PARTICLES};
+use crate::planet::Planet;
+use crate::planet_field::PlanetField;
+use crate::player::{GrabMode, Player};
+use crate::projectile::{Projectile, ProjectileSettings, PROJECTILE_RADIUS, PROJECTILE_SPEED};
+use crate::random_generator::{get_rand_generator, next_seed};
+use crate::score::Score;
+use crate::spatial_hash::SpatialHash;
+
+/// Length of one simulation step in seconds.
+pub const FIXED_DELTA_TIME: f32 = 1f32 / 120f32;
+
+/// Cell size of the broadphase grid, the diameter of the biggest planets.
+pub const BROADPHASE_CELL_SIZE: f32 = 200f32;
+
+/// The whole game simulation. Knows nothing about windows, keyboards or drawing, so it can be
+/// stepped from the game loop as well as from tests and tools without a display.
+pub struct World {
+    pub player: Player,
+    pub planets: Vec<Planet>,
+    pub planet_field: PlanetField,
+    pub projectiles: Vec<Projectile>,
+    pub enemies: Vec<Enemy>,
+    pub difficulty: DifficultyProfile,
+    pub score: Score,
+    pub bottom_danger_zone: DangerZone,
+    pub left_danger_zone: DangerZone,
+    pub right_danger_zone: DangerZone,
+    /// What the zones were made with, to re-tune them by how much that changes.
+    pub zone_settings: ZoneSettings,
+    /// Planets attract each other and the player. Off by default, then planets just drift.
+    pub gravity_enabled: bool,
+    pub collision_settings: CollisionSettings,
+    pub projectile_settings: ProjectileSettings,
+    pub spatial_hash: SpatialHash,
+    /// Every entity's particles, only for looks. Nothing in the simulation depends on them.
+    pub particles: ParticlePool,
+    /// The planet grabbing would hold on to right now, for showing it.
+    pub grab_candidate: Option<usize>,
+    // reused every step so finding collisions and grab targets and despawning don't allocate
+    nearby_planets: Vec<usize>,
+    collision_pairs: Vec<(usize, usize)>,
+    new_planet_indices: Vec<Option<usize>>,
+}
+
+impl World {
+    /// Two worlds built from the same `seed` and `config` and stepped with the same inputs and
+    /// delta times stay bit-identical.
+    pub fn new(seed: u64, config: &Config) -> Self {
+        let rand_num_generator: RandGenerator = get_rand_generator(seed);
+
+        let difficulty = config.difficulty.clone();
+        let zone = &config.zone;
+        let mut planet_field =
+            PlanetField::new(next_seed(&rand_num_generator), config.planet, config.enemy);
+        let mut planets: Vec<Planet> = Vec::new();
+        let mut enemies: Vec<Enemy> = Vec::new();
+        planet_field.generate_up_to(0f32, &difficulty, &mut planets, &mut enemies);
+
+        Self {
+            player: Player::new(config.player),
+            planets,
+            planet_field,
+            projectiles: Vec::new(),
+            enemies,
+            difficulty,
+            score: Score::default(),
+            bottom_danger_zone: DangerZone::new(
+                zone.bottom_position.into(),
+                zone.bottom_size.into(),
+                Vec2 {
+                    x: 0f32,
+                    y: -zone.bottom_speed,
+                },
+                &zone.bottom_particles,
+                ParticleGroup::BottomZone,
+            ),
+            left_danger_zone: DangerZone::new(
+                Vec2 {
+                    x: -zone.side_distance,
+                    y: 0f32,
+                },
+                zone.side_size.into(),
+                Vec2 { x: 0f32, y: 0f32 },
+                &zone.side_particles,
+                ParticleGroup::SideZone,
+            ),
+            right_danger_zone: DangerZone::new(
+                Vec2 {
+                    x: zone.side_distance,
+                    y: 0f32,
+                },
+                zone.side_size.into(),
+                Vec2 { x: 0f32, y: 0f32 },
+                &zone.side_particles,
+                ParticleGroup::SideZone,
+            ),
+            zone_settings: config.zone,
+            gravity_enabled: false,
+            collision_settings: config.collision,
+            projectile_settings: config.projectile,
+            spatial_hash: SpatialHash::new(BROADPHASE_CELL_SIZE),
+            particles: ParticlePool::new(MAX_PARTICLES, next_seed(&rand_num_generator)),
+            grab_candidate: None,
+            nearby_planets: Vec::new(),
+            collision_pairs: Vec::new(),
+            new_planet_indices: Vec::new(),
+        }
+    }
+
+    /// Re-tunes everything that's already there to `config`, so changes show up without
+    /// starting a new run. Things are only made bigger, smaller or differently looking, the
+    /// layout of the run stays the same.
+    pub fn apply_config(self: &mut Self, config: &Config) {
+        self.player.apply_settings(config.player);
+        for planet in &mut self.planets {
+            planet.apply_settings(&self.planet_field.planet_settings, &config.planet);
+        }
+        self.planet_field.planet_settings = config.planet;
+        for enemy in &mut self.enemies {
+            enemy.apply_settings(&config.enemy);
+        }
+        self.planet_field.enemy_settings = config.enemy;
+        for projectile in &mut self.projectiles {
+            config.projectile.trail_particles.apply_to(
+                &mut projectile.particle_controller_trails,
+                PROJECTILE_RADIUS,
+            );
+        }
+        self.projectile_settings = config.projectile;
+        let zone = &config.zone;
+        self.bottom_danger_zone
+            .apply_settings(zone.bottom_size.into(), &zone.bottom_particles);
+        self.left_danger_zone
+            .apply_settings(zone.side_size.into(), &zone.side_particles);
+        self.right_danger_zone
+            .apply_settings(zone.side_size.into(), &zone.side_particles);
+        // the zones go on from where they are, moved as much as their start moved. Once the
+        // player is caught, the zones are busy with that
+        if !self.player.is_dead {
+            let old_zone = &self.zone_settings;
+            let bottom_zone = &mut self.bottom_danger_zone;
+            bottom_zone.position.y += zone.bottom_position[1] - old_zone.bottom_position[1];
+            // it has sped up since the start, it keeps as much of that
+            bottom_zone.velocity.y = if old_zone.bottom_speed > 0f32 {
+                bottom_zone.velocity.y * zone.bottom_speed / old_zone.bottom_speed
+            } else {
+                -zone.bottom_speed
+            };
+            self.left_danger_zone.position.x = -zone.side_distance;
+            self.right_danger_zone.position.x = zone.side_distance;
+        }
+        self.zone_settings = *zone;
+        self.collision_settings = config.collision;
+        self.difficulty = config.difficulty.clone();
+    }
+
+    pub fn step(self: &mut Self, input: &Input, delta_time: f32) {
+        self.player.previous_position = self.player.position;
+        for planet in &mut self.planets {
+            planet.previous_position = planet.position;
+        }
+        self.bottom_danger_zone.previous_position = self.bottom_danger_zone.position;
+        self.left_danger_zone.previous_position = self.left_danger_zone.position;
+        self.right_danger_zone.previous_position = self.right_danger_zone.position;
+        for projectile in &mut self.projectiles {
+            projectile.previous_position = projectile.position;
+        }
+        for enemy in &mut self.enemies {
+            enemy.previous_position = enemy.position;
+        }
+
+        self.planet_field.generate_up_to(
+            self.player.position.y,
+            &self.difficulty,
+            &mut self.planets,
+            &mut self.enemies,
+        );
+        self.despawn_planets();
+        self.despawn_enemies();
+
+        if !self.player.is_dead && input.grab_pressed {
+            self.spatial_hash.rebuild(
+                self.planets
+                    .iter()
+                    .map(|planet| (planet.position, planet.radius)),
+            );
+            match self.find_grab_target(input.aim) {
+                Some(planet_index) => self.player.grab(planet_index, &self.planets),
+                None => self.player.linked_planet_index = None,
+            }
+        }
+
+        let player = &mut self.player;
+        let planets = &mut self.planets;
+        let particles = &mut self.particles;
+
+        if player.is_dead || input.grab_released {
+            let was_linked = player.linked_planet_index.is_some();
+            player.let_go_of_planet();
+            if was_linked && !player.is_dead {
+                self.score.add_slingshot(player.velocity.length());
+            }
+        }
+
+        if self.gravity_enabled {
+            apply_gravity(planets, player, delta_time);
+        }
+
+        player.update(planets, particles, input, delta_time);
+        if input.shoot_down && player.can_shoot() {
+            let direction = player.shoot(input.aim);
+            self.projectiles.push(Projectile::new(
+                player.position + direction * (player.radius + PROJECTILE_RADIUS),
+                player.velocity + direction * PROJECTILE_SPEED,
+                &self.projectile_settings,
+            ));
+        }
+        if !player.is_dead {
+            self.score.update_height(player.position.y);
+        }
+
+        self.bottom_danger_zone.update_as_bottom_zone(
+            particles,
+            delta_time,
+            player.position.x,
+            self.difficulty
+                .bottom_zone_acceleration
+                .value_at(altitude(player.position.y)),
+        );
+        self.bottom_danger_zone
+            .check_and_handle_player_collision(player);
+        if !player.is_dead {
+            self.left_danger_zone
+                .update_as_side_zone(particles, delta_time, player.position.y);
+            self.right_danger_zone
+                .update_as_side_zone(particles, delta_time, player.position.y);
+            self.left_danger_zone
+                .check_and_handle_player_collision(player);
+            self.right_danger_zone
+                .check_and_handle_player_collision(player);
+            if player.is_dead {
+                self.bottom_danger_zone.position.y = player.position.y + 600f32;
+            }
+        }
+
+        for planet in planets.iter_mut() {
+            planet.update(particles, delta_time);
+        }
+        for projectile in &mut self.projectiles {
+            projectile.update(particles, delta_time);
+        }
+        for enemy in &mut self.enemies {
+            enemy.update(player, planets, &self.projectiles, particles, delta_time);
+            if !player.is_dead {
+                enemy.check_and_handle_player_collision(player);
+            }
+        }
+        particles.update(delta_time);
+
+        self.spatial_hash.rebuild(
+            planets
+                .iter()
+                .map(|planet| (planet.position, planet.radius)),
+        );
+
+        self.nearby_planets.clear();
+        self.spatial_hash
+            .query_circle(player.position, player.radius, &mut self.nearby_planets);
+        self.nearby_planets.sort_unstable();
+        for &index in &self.nearby_planets {
+            let planet = &mut planets[index];
+            if (player.position - planet.position).length() < player.radius + planet.radius {
+                resolve_collision(
+                    &mut player.body(),
+                    &mut planet.body(),
+                    &self.collision_settings,
+                );
+            }
+        }
+
+        self.collision_pairs.clear();
+        self.spatial_hash
+            .overlapping_pairs(&mut self.collision_pairs);
+        // the hash hands them out in no particular order, but resolving them in a different
+        // order would give a different result
+        self.collision_pairs.sort_unstable();
+        for &(i, j) in &self.collision_pairs {
+            let (left, right) = planets.split_at_mut(j);
+            let (planet, other) = (&mut left[i], &mut right[0]);
+            // earlier collisions may have pushed them apart already
+            if (planet.position - other.position).length() < planet.radius + other.radius {
+                resolve_collision(
+                    &mut planet.body(),
+                    &mut other.body(),
+                    &self.collision_settings,
+                );
+            }
+        }
+
+        self.hit_enemies_with_projectiles();
+        self.hit_planets_with_projectiles();
+
+        self.grab_candidate = if self.player.is_dead || self.player.linked_planet_index.is_some() {
+            None
+        } else {
+            self.find_grab_target(input.aim)
+        };
+    }
+
+    /// The planet grabbing would hold on to, using the spatial hash as it is. With
+    /// `GrabMode::Aimed` that's the first planet in range hit by the ray from the player toward
+    /// `aim` (or the way they fly, without one), or if the ray misses them all, the one that
+    /// comes closest to it.
+    pub fn find_grab_target(self: &mut Self, aim: Option<Vec2>) -> Option<usize> {
+        let player = &self.player;
+        if player.settings.grab_mode == GrabMode::Nearest {
+            return self
+                .spatial_hash
+                .nearest(player.position, &mut self.nearby_planets);
+        }
+
+        let direction = aim
+            .map_or(player.velocity, |aim| aim - player.position)
+            .try_normalize()?;
+        self.nearby_planets.clear();
+        self.spatial_hash.query_circle(
+            player.position,
+            player.settings.max_grab_range,
+            &mut self.nearby_planets,
+        );
+        self.nearby_planets.sort_unstable();
+
+        // (how far the ray misses the planet, how far along the ray it is), lowest first
+        let mut best: Option<(f32, f32, usize)> = None;
+        for &index in &self.nearby_planets {
+            let planet = &self.planets[index];
+            let to_planet = planet.position - player.position;
+            let along = to_planet.dot(direction);
+            if along <= 0f32 || to_planet.length() - planet.radius > player.settings.max_grab_range
+            {
+                continue;
+            }
+            let miss = ((to_planet - direction * along).length() - planet.radius).max(0f32);
+            if best.is_none_or(|(best_miss, best_along, _)| {
+                miss < best_miss || (miss == best_miss && along < best_along)
+            }) {
+                best = Some((miss, along, index));
+            }
+        }
+        best.map(|(_, _, index)| index)
+    }
+
+    // a hit takes one health off the first enemy the projectile touches and uses it up
+    fn hit_enemies_with_projectiles(self: &mut Self) {
+        for projectile in &mut self.projectiles {
+            for enemy in &mut self.enemies {
+                if projectile.hits(enemy.position, enemy.radius) {
+                    if enemy.hit() && !self.player.is_dead {
+                        self.score.add_enemy_kill();
+                    }
+                    projectile.time_left = 0f32;
+                    break;
+                }
+            }
+        }
+        self.enemies.retain(|enemy| !enemy.is_destroyed());
+        self.projectiles
+            .retain(|projectile| !projectile.is_expired());
+    }
+
+    // a hit knocks the planet away and uses up the projectile
+    fn hit_planets_with_projectiles(self: &mut Self) {
+        for projectile in &mut self.projectiles {
+            self.nearby_planets.clear();
+            self.spatial_hash.query_circle(
+                projectile.position,
+                projectile.radius,
+                &mut self.nearby_planets,
+            );
+            self.nearby_planets.sort_unstable();
+            for &index in &self.nearby_planets {
+                let planet = &mut self.planets[index];
+                if projectile.hits(planet.position, planet.radius) {
+                    planet.velocity += projectile.velocity * projectile.mass / planet.mass;
+                    projectile.time_left = 0f32;
+                    break;
+                }
+            }
+        }
+        self.projectiles
+            .retain(|projectile| !projectile.is_expired());
+    }
+
+    fn despawn_planets(self: &mut Self) {
+        let bottom_zone_lower_edge =
+            self.bottom_danger_zone.position.y + self.bottom_danger_zone.size.y * 0.5;
+        // removing planets shifts the indices of the ones after them, so the linked planet's
+        // and the orbiters' indices have to follow along
+        let new_indices = &mut self.new_planet_indices;
+        new_indices.clear();
+        let mut kept = 0;
+        for planet in &self.planets {
+            if self
+                .planet_field
+                .is_out_of_range(planet, bottom_zone_lower_edge)
+            {
+                new_indices.push(None);
+            } else {
+                new_indices.push(Some(kept));
+                kept += 1;
+            }
+        }
+        if kept == self.planets.len() {
+            return;
+        }
+
+        let mut index = 0;
+        self.planets.retain(|_| {
+            index += 1;
+            new_indices[index - 1].is_some()
+        });
+        self.player.linked_planet_index = self
+            .player
+            .linked_planet_index
+            .and_then(|index| new_indices[index]);
+        for enemy in &mut self.enemies {
+            if let EnemyKind::Orbiter { planet_index } = &mut enemy.kind {
+                match new_indices[*planet_index] {
+                    Some(new_index) => *planet_index = new_index,
+                    None => enemy.lose_planet(),
+                }
+            }
+        }
+    }
+
+    fn despawn_enemies(self: &mut Self) {
+        let bottom_zone_lower_edge =
+            self.bottom_danger_zone.position.y + self.bottom_danger_zone.size.y * 0.5;
+        let planet_field = &self.planet_field;
+        self.enemies
+            .retain(|enemy| !planet_field.is_enemy_out_of_range(enemy, bottom_zone_lower_edge));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::danger_zone::CAUGHT_PARTICLE_LIFESPAN;
+    use crate::difficulty::Curve;
+
+    fn grab_input() -> Input {
+        Input {
+            grab_pressed: true,
+            grab_down: true,
+            ..Input::default()
+        }
+    }
+
+    #[test]
+    fn steps_headless() {
+        let mut world = World::new(1, &Config::default());
+        assert!(!world.planets.is_empty());
+        world.step(&grab_input(), FIXED_DELTA_TIME);
+        assert!(world.player.linked_planet_index.is_some());
+        // swing around for a bit, then let go
+        for i in 0..600 {
+            let input = Input {
+                grab_down: i < 300,
+                grab_released: i == 300,
+                ..Input::default()
+            };
+            world.step(&input, FIXED_DELTA_TIME);
+        }
+        assert_eq!(world.player.linked_planet_index, None);
+        assert_ne!(world.player.position, Vec2::ZERO);
+        assert!(world.player.position.is_finite());
+        assert!(world
+            .planets
+            .iter()
+            .all(|planet| planet.position.is_finite()));
+    }
+
+    #[test]
+    fn grab_without_planets_holds_on_to_nothing() {
+        for grab_mode in [GrabMode::Nearest, GrabMode::Aimed] {
+            let mut config = Config::default();
+            config.player.grab_mode = grab_mode;
+            let mut world = World::new(1, &config);
+            world.planets.clear();
+            world.enemies.clear();
+            world.step(&grab_input(), FIXED_DELTA_TIME);
+            assert_eq!(world.player.linked_planet_index, None);
+            assert_eq!(world.grab_candidate, None);
+        }
+    }
+
+    #[test]
+    fn reloading_moves_the_zones_and_keeps_the_caught_particles() {
+        let mut world = World::new(1, &Config::default());
+        let mut config = Config::default();
+        config.zone.side_distance += 100f32;
+        config.zone.bottom_position[1] += 50f32;
+        config.zone.bottom_speed *= 2f32;
+        let bottom_y = world.bottom_danger_zone.position.y;
+        let bottom_velocity = world.bottom_danger_zone.velocity.y;
+        world.apply_config(&config);
+        assert_eq!(
+            world.left_danger_zone.position.x,
+            -config.zone.side_distance
+        );
+        assert_eq!(
+            world.right_danger_zone.position.x,
+            config.zone.side_distance
+        );
+        assert_eq!(world.bottom_danger_zone.position.y, bottom_y + 50f32);
+        assert_eq!(world.bottom_danger_zone.velocity.y, bottom_velocity * 2f32);
+
+        // put the player in the bottom zone
+        world.player.position = world.bottom_danger_zone.position;
+        world.step(&Input::default(), FIXED_DELTA_TIME);
+        assert!(world.player.is_dead);
+        world.apply_config(&Config::default());
+        assert_eq!(
+            world.bottom_danger_zone.particle_controller.effect.lifespan,
+            CAUGHT_PARTICLE_LIFESPAN
+        );
+    }
+
+    // swinging around one planet, with no zones, enemies or other planets to bump into
+    fn world_holding_one_planet(mut config: Config) -> World {
+        config.zone.bottom_position[1] = 1e6;
+        config.zone.bottom_speed = 0f32;
+        config.zone.side_distance = 1e6;
+        let mut world = World::new(1, &config);
+        world.step(&grab_input(), FIXED_DELTA_TIME);
+        let planet_index = world.player.linked_planet_index.unwrap();
+        world.planets.swap(0, planet_index);
+        world.planets.truncate(1);
+        world.enemies.clear();
+        world.player.linked_planet_index = Some(0);
+        world
+    }
+
+    // how much further from the planet the player is than the rope is long
+    fn rope_stretch(world: &World) -> f32 {
+        let planet_index = world.player.linked_planet_index.unwrap();
+        (world.planets[planet_index].position - world.player.position).length()
+            - world.player.rope_length
+    }
+
+    #[test]
+    fn rope_never_gets_longer_than_it_is() {
+        let mut world = world_holding_one_planet(Config::default());
+        for step in 0..2400 {
+            let phase = step % 600;
+            let input = Input {
+                grab_down: true,
+                boost_down: phase < 100,
+                reel_in: (200..300).contains(&phase),
+                reel_out: (400..500).contains(&phase),
+                ..Input::default()
+            };
+            world.step(&input, FIXED_DELTA_TIME);
+            assert!(rope_stretch(&world) < 1e-3, "step {}", step);
+        }
+    }
+
+    #[test]
+    fn reeling_stays_between_the_planet_and_max_rope_length() {
+        let mut world = world_holding_one_planet(Config::default());
+        let min_length = world.planets[0].radius + world.player.radius;
+        let max_length = world.player.settings.max_rope_length;
+        let reel = |world: &mut World, reel_in: bool| {
+            let input = Input {
+                grab_down: true,
+                reel_in,
+                reel_out: !reel_in,
+                ..Input::default()
+            };
+            for _ in 0..1200 {
+                world.step(&input, FIXED_DELTA_TIME);
+                assert!((min_length..=max_length).contains(&world.player.rope_length));
+            }
+        };
+        reel(&mut world, true);
+        assert_eq!(world.player.rope_length, min_length);
+        reel(&mut world, false);
+        assert_eq!(world.player.rope_length, max_length);
+    }
+
+    #[test]
+    fn rope_without_elasticity_holds_its_length() {
+        let max_stretch = |rope_elasticity: f32| {
+            let mut config = Config::default();
+            config.player.rope_elasticity = rope_elasticity;
+            let mut world = world_holding_one_planet(config);
+            let rope_length = world.player.rope_length;
+            let mut max_stretch = 0f32;
+            for _ in 0..1200 {
+                let input = Input {
+                    grab_down: true,
+                    ..Input::default()
+                };
+                world.step(&input, FIXED_DELTA_TIME);
+                assert_eq!(world.player.rope_length, rope_length);
+                max_stretch = max_stretch.max(rope_stretch(&world));
+            }
+            max_stretch
+        };
+        assert!(max_stretch(0f32) < 1e-3);
+        assert!(max_stretch(0.5) > 1f32);
+    }
+
+    #[test]
+    fn despawning_planets_keeps_pointing_at_the_same_planets() {
+        let mut config = Config::default();
+        config.difficulty.enemies_per_chunk = Curve::constant(3f32);
+        let mut world = World::new(5, &config);
+        // hold on to the highest planet, it stays
+        let highest = (0..world.planets.len())
+            .min_by(|&i, &j| {
+                world.planets[i]
+                    .position
+                    .y
+                    .total_cmp(&world.planets[j].position.y)
+            })
+            .unwrap();
+        world.player.grab(highest, &world.planets);
+        let linked_position = world.planets[highest].position;
+        let orbited_positions: Vec<Option<Vec2>> = world
+            .enemies
+            .iter()
+            .map(|enemy| match enemy.kind {
+                EnemyKind::Orbiter { planet_index } => Some(world.planets[planet_index].position),
+                _ => None,
+            })
+            .collect();
+
+        // the lower planets fall out of range
+        let planet_count = world.planets.len();
+        world.bottom_danger_zone.position.y = -4200f32;
+        world.despawn_planets();
+        assert!(world.planets.len() < planet_count);
+
+        let linked_index = world.player.linked_planet_index.unwrap();
+        assert_eq!(world.planets[linked_index].position, linked_position);
+        let mut kept_orbiters = 0;
+        for (enemy, orbited_position) in world.enemies.iter().zip(&orbited_positions) {
+            match enemy.kind {
+                EnemyKind::Orbiter { planet_index } => {
+                    kept_orbiters += 1;
+                    assert_eq!(
+                        Some(world.planets[planet_index].position),
+                        *orbited_position
+                    );
+                }
+                // without its planet it goes after the player
+                EnemyKind::Chaser if orbited_position.is_some() => {}
+                _ => assert!(orbited_position.is_none()),
+            }
+        }
+        assert!(kept_orbiters > 0);
+        assert!(kept_orbiters < orbited_positions.iter().flatten().count());
+    }
+
+    // some of everything: grabbing, swinging, boosting, shooting at an aim and reeling
+    fn scripted_input(step: u32) -> Input {
+        let phase = step % 240;
+        Input {
+            grab_pressed: phase == 0,
+            grab_released: phase == 150,
+            grab_down: phase < 150,
+            boost_down: phase > 200,
+            reel_in: phase < 60,
+            reel_out: (100..150).contains(&phase),
+            shoot_down: step % 50 < 5,
+            aim: (step % 100 < 50).then(|| Vec2::new(step as f32, -500f32)),
+        }
+    }
+
+    fn position_bits(world: &World) -> Vec<(u32, u32)> {
+        std::iter::once(world.player.position)
+            .chain(world.planets.iter().map(|planet| planet.position))
+            .chain(world.enemies.iter().map(|enemy| enemy.position))
+            .chain(
+                world
+                    .projectiles
+                    .iter()
+                    .map(|projectile| projectile.position),
+            )
+            .map(|position| (position.x.to_bits(), position.y.to_bits()))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_and_inputs_stay_bit_identical() {
+        let config = Config::default();
+        let mut worlds = [World::new(42, &config), World::new(42, &config)];
+        for world in &mut worlds {
+            world.gravity_enabled = true;
+        }
+        for step in 0..1200 {
+            for world in &mut worlds {
+                world.step(&scripted_input(step), FIXED_DELTA_TIME);
+            }
+            assert_eq!(
+                position_bits(&worlds[0]),
+                position_bits(&worlds[1]),
+                "step {}",
+                step
+            );
+        }
+        assert_eq!(
+            worlds[0].player.velocity.x.to_bits(),
+            worlds[1].player.velocity.x.to_bits()
+        );
+        assert_eq!(
+            worlds[0].player.velocity.y.to_bits(),
+            worlds[1].player.velocity.y.to_bits()
+        );
+    }
+}